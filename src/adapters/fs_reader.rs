@@ -1,14 +1,21 @@
-use crate::core::content::{ContentType, FileContext};
-use crate::core::file::FileNode;
+use crate::core::config::OversizePolicy;
+use crate::core::content::{ContentType, FileContext, SkipReason};
+use crate::core::file::{format_size, FileKind, FileNode, Fragment};
+use crate::core::skeleton::symbol_lines;
 use crate::ports::reader::FileReader;
+use crate::ports::tokenizer::TokenCounter;
+use anyhow::Result;
+use encoding_rs::UTF_8;
 use std::path::Path;
+use std::sync::Arc;
 
 // Import strategies
 use crate::adapters::parsers::docx::DocxParser;
 use crate::adapters::parsers::excel::ExcelParser;
-use crate::adapters::parsers::fallback::PlainTextParser;
+use crate::adapters::parsers::fallback::{DecodedText, PlainTextParser};
 use crate::adapters::parsers::pdf::PdfParser;
 use crate::adapters::parsers::FileParser;
+use crate::adapters::tokenizer::BpeTokenCounter;
 
 /// Implementation of FileReader that acts as a Router for specific parsers.
 pub struct FsReader {
@@ -16,6 +23,10 @@ pub struct FsReader {
     docx_parser: DocxParser,
     excel_parser: ExcelParser,
     text_parser: PlainTextParser,
+    token_counter: Arc<dyn TokenCounter>,
+    max_file_size: Option<u64>,
+    oversize: OversizePolicy,
+    normalize_eol: bool,
 }
 
 impl Default for FsReader {
@@ -27,61 +38,187 @@ impl Default for FsReader {
 impl FsReader {
     /// Creates a new instance of FsReader with all parsers initialized.
     pub fn new() -> Self {
+        Self::with_token_counter(Arc::new(BpeTokenCounter::default()))
+    }
+
+    /// Creates a FsReader that counts tokens with the given counter.
+    pub fn with_token_counter(token_counter: Arc<dyn TokenCounter>) -> Self {
         Self {
             pdf_parser: PdfParser::new(),
             docx_parser: DocxParser::new(),
             excel_parser: ExcelParser::new(),
             text_parser: PlainTextParser::new(),
+            token_counter,
+            max_file_size: None,
+            oversize: OversizePolicy::Skip,
+            normalize_eol: false,
         }
     }
 
+    /// Applies `--max-file-size`: larger files are skipped or, for text files
+    /// under the truncate policy, cut down to the limit.
+    pub fn with_size_limit(mut self, max_file_size: Option<u64>, oversize: OversizePolicy) -> Self {
+        self.max_file_size = max_file_size;
+        self.oversize = oversize;
+        self
+    }
+
+    /// Converts CRLF line endings to LF in every text that is read.
+    pub fn with_normalize_eol(mut self, normalize_eol: bool) -> Self {
+        self.normalize_eol = normalize_eol;
+        self
+    }
+
     /// Infers programming language from extension.
-    fn detect_language(&self, path: &Path) -> String {
+    pub(crate) fn detect_language(&self, path: &Path) -> String {
         path.extension()
             .and_then(|ext| ext.to_str())
             .unwrap_or("text")
             .to_lowercase()
     }
-
-    /// Simple heuristic for token counting.
-    fn estimate_tokens(&self, text: &str) -> usize {
-        text.len() / 3
-    }
 }
 
 impl FileReader for FsReader {
     /// Reads the file from disk, routing to specific parsers based on extension.
+    ///
+    /// Files the scanner classified as binary are never opened, and files over
+    /// the size limit are skipped or truncated without being read in full.
     fn read_file(&self, node: &FileNode) -> FileContext {
+        self.read_from(node, Source::Disk)
+    }
+}
+
+/// Where the bytes of a file come from.
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Disk,
+    /// Contents already loaded, e.g. a git blob.
+    Memory(&'a [u8]),
+}
+
+impl FsReader {
+    /// Same as `read_file`, for a file whose contents are already in memory.
+    pub fn read_bytes(&self, node: &FileNode, bytes: &[u8]) -> FileContext {
+        self.read_from(node, Source::Memory(bytes))
+    }
+
+    fn parse_with(
+        &self,
+        parser: &dyn FileParser,
+        node: &FileNode,
+        source: Source,
+    ) -> Result<String> {
+        match source {
+            Source::Disk => parser.parse(&node.path),
+            Source::Memory(bytes) => parser.parse_bytes(bytes),
+        }
+    }
+
+    fn read_head(&self, node: &FileNode, source: Source, limit: u64) -> Result<DecodedText> {
+        match source {
+            Source::Disk => self.text_parser.parse_head(&node.path, limit),
+            Source::Memory(bytes) => {
+                let end = bytes.len().min(limit as usize);
+                self.text_parser.decode_head(&bytes[..end])
+            }
+        }
+    }
+
+    fn read_from(&self, node: &FileNode, source: Source) -> FileContext {
         let extension = self.detect_language(&node.path);
+        let is_document = ["pdf", "docx", "xlsx", "xls"].contains(&extension.as_str());
+        let over_limit = self.max_file_size.filter(|&limit| node.size > limit);
+        let truncate = !is_document && self.oversize == OversizePolicy::Truncate;
 
-        let parser_result = match extension.as_str() {
-            "pdf" => self.pdf_parser.parse(&node.path),
-            "docx" => self.docx_parser.parse(&node.path),
-            "xlsx" | "xls" => self.excel_parser.parse(&node.path),
-            _ => self.text_parser.parse(&node.path),
+        // Document parsers always produce UTF-8; `encoding` is only known for
+        // files read through the text parser.
+        let mut encoding = None;
+        let parser_result = match over_limit {
+            _ if node.kind == FileKind::Binary => Err(ContentType::Binary),
+            Some(limit) if !truncate => Err(ContentType::Skipped(SkipReason::TooLarge {
+                size: node.size,
+                limit,
+            })),
+            Some(limit) => match self.read_head(node, source, limit) {
+                Ok(head) => {
+                    encoding = Some(head.encoding);
+                    Ok(format!(
+                        "{}[... file truncated at {} of {} ...]\n",
+                        head.text,
+                        format_size(limit),
+                        format_size(node.size)
+                    ))
+                }
+                Err(_) => Err(ContentType::Binary),
+            },
+            None => match extension.as_str() {
+                "pdf" => self.parse_with(&self.pdf_parser, node, source),
+                "docx" => self.parse_with(&self.docx_parser, node, source),
+                "xlsx" | "xls" => self.parse_with(&self.excel_parser, node, source),
+                _ => match source {
+                    Source::Disk => self.text_parser.read(&node.path),
+                    Source::Memory(bytes) => self.text_parser.read_bytes(bytes),
+                }
+                .map(|decoded| {
+                    encoding = Some(decoded.encoding);
+                    decoded.text
+                }),
+            }
+            .map_err(|e| {
+                if is_document {
+                    ContentType::Error(e.to_string())
+                } else {
+                    ContentType::Binary
+                }
+            }),
         };
 
-        let (content, tokens) = match parser_result {
+        let mut lines = None;
+        let mut start_byte = 0;
+        let (content, tokens) = match parser_result.and_then(|text| {
+            let range = match &node.fragment {
+                None => return Ok(text),
+                Some(Fragment::Lines(range)) => *range,
+                Some(Fragment::Symbol(name)) => symbol_lines(&text, &extension, name)
+                    .ok_or_else(|| ContentType::Error(format!("symbol `{}` not found", name)))?,
+            };
+            lines = Some(range);
+            let bytes = range.byte_range(&text);
+            start_byte = bytes.start;
+            Ok(text[bytes].to_string())
+        }) {
             Ok(text) => {
-                let count = self.estimate_tokens(&text);
-                (ContentType::Text(text), count)
-            }
-            Err(e) => {
-                if ["pdf", "docx", "xlsx", "xls"].contains(&extension.as_str()) {
-                    (ContentType::Error(e.to_string()), 0)
+                let text = if self.normalize_eol {
+                    text.replace("\r\n", "\n")
                 } else {
-                    (ContentType::Binary, 0)
-                }
+                    text
+                };
+                let count = self.token_counter.count(&text);
+                (ContentType::Text(text), count)
             }
+            Err(content) => (content, 0),
         };
 
-        FileContext::new(
+        let mut context = FileContext::new(
             node.path.clone(),
             node.relative_path.clone(),
             content,
             extension,
             tokens,
-        )
+        );
+        context.truncated = over_limit.is_some() && matches!(context.content, ContentType::Text(_));
+        context.encoding = encoding
+            .filter(|&encoding| encoding != UTF_8)
+            .map(|encoding| encoding.name().to_string());
+        context.link_target = node.link_target.clone();
+        context.root = node.root.clone();
+        context.lines = lines;
+        context.start_byte = start_byte;
+        context.modified = node.modified;
+        if let Some(Fragment::Symbol(name)) = &node.fragment {
+            context.symbol = Some(name.clone());
+        }
+        context
     }
 }
 
@@ -90,6 +227,7 @@ mod tests {
     use super::*;
     use std::fs::File;
     use std::io::Write;
+    use std::path::PathBuf;
     use tempfile::tempdir;
 
     #[test]
@@ -108,4 +246,39 @@ mod tests {
             _ => panic!("Should be detected as text"),
         }
     }
+
+    #[test]
+    fn test_read_size_limit() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("big.log");
+        std::fs::write(&file_path, "line one\nline two\nline three\n").unwrap();
+        let node = FileNode::new(file_path.clone(), PathBuf::from("big.log"))
+            .with_metadata(29, FileKind::Text);
+
+        let skipped = FsReader::new()
+            .with_size_limit(Some(12), OversizePolicy::Skip)
+            .read_file(&node);
+        assert_eq!(
+            skipped.content,
+            ContentType::Skipped(SkipReason::TooLarge {
+                size: 29,
+                limit: 12
+            })
+        );
+
+        let truncated = FsReader::new()
+            .with_size_limit(Some(12), OversizePolicy::Truncate)
+            .read_file(&node);
+        assert!(truncated.truncated);
+        assert_eq!(
+            truncated.content,
+            ContentType::Text("line one\n[... file truncated at 12 B of 29 B ...]\n".into())
+        );
+
+        let binary = node.with_metadata(29, FileKind::Binary);
+        assert_eq!(
+            FsReader::new().read_file(&binary).content,
+            ContentType::Binary
+        );
+    }
 }
@@ -1,13 +1,27 @@
 // 🏛️ ARCHITECTURE ROLE: ADAPTER
-use anyhow::Result;
-use ignore::{DirEntry, WalkBuilder};
-use std::path::Path;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, Walk, WalkBuilder};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, warn};
 
-use crate::core::config::ContextConfig;
-use crate::core::file::FileNode;
+use crate::adapters::git;
+use crate::adapters::sniff::sniff_file;
+use crate::core::config::{ContextConfig, GitSelection, RelatedFiles, TimeFilter};
+use crate::core::file::{FileKind, FileNode};
+use crate::core::pattern::PathMatcher;
+use crate::core::related::{imports_any, module_name};
 use crate::ports::scanner::ProjectScanner;
 
+/// Per-directory ignore file with gitignore syntax for rules that only concern
+/// the context report. Honoured even with `--no-ignore`.
+pub const CONTEXT_IGNORE_FILE: &str = ".contextignore";
+
 #[derive(Default)]
 pub struct FsScanner;
 
@@ -16,75 +30,217 @@ impl FsScanner {
         Self
     }
 
-    fn is_noise(entry: &DirEntry) -> bool {
-        let file_name = entry.file_name().to_string_lossy();
-
-        const NOISE_FILES: &[&str] = &[
-            "Cargo.lock",
-            "package-lock.json",
-            "yarn.lock",
-            "pnpm-lock.yaml",
-            "poetry.lock",
-            "Pipfile.lock",
-            ".DS_Store",
-            "Thumbs.db",
-        ];
-
-        const NOISE_DIRS: &[&str] = &[
-            ".git",
-            ".svn",
-            ".hg",
-            "target",
-            "build",
-            "dist",
-            "node_modules",
-            "__pycache__",
-            ".venv",
-            "venv",
-            ".idea",
-            ".vscode",
-        ];
-
-        if NOISE_FILES.contains(&file_name.as_ref()) {
-            return true;
-        }
+    /// Builds the directory walker shared by `scan` and `list_noise`. Entries
+    /// matching the noise rules are pruned and reported to `on_noise`.
+    fn walker<F>(config: &ContextConfig, on_noise: F) -> Result<Walk>
+    where
+        F: Fn(&DirEntry, bool) + Send + Sync + 'static,
+    {
+        let mut builder = WalkBuilder::new(&config.root_path);
 
-        if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false)
-            && NOISE_DIRS.contains(&file_name.as_ref())
-        {
-            return true;
+        builder
+            .standard_filters(true)
+            .hidden(!config.include_hidden)
+            .git_ignore(!config.no_ignore)
+            .git_global(!config.no_ignore)
+            .ignore(!config.no_ignore)
+            .follow_links(config.follow_symlinks)
+            .add_custom_ignore_filename(CONTEXT_IGNORE_FILE);
+
+        if let Some(depth) = config.max_depth {
+            builder.max_depth(Some(depth));
         }
 
-        false
+        let noise = config.noise.clone();
+        let ignore_files = Self::build_ignore_files(config)?;
+        builder.filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+            if entry.depth() > 0 && noise.is_noise(&entry.file_name().to_string_lossy(), is_dir) {
+                on_noise(entry, is_dir);
+                return false;
+            }
+            !ignore_files.matched(entry.path(), is_dir).is_ignore()
+        });
+
+        Ok(builder.build())
     }
 
-    /// Checks filters: Extensions and Paths.
-    fn matches_filters(path: &Path, config: &ContextConfig) -> bool {
-        let path_str = path.to_string_lossy();
+    /// Lists the paths the noise rules drop (relative to the root), without
+    /// descending into noise directories. Used by `--show-noise`.
+    pub fn list_noise(&self, config: &ContextConfig) -> Result<Vec<(PathBuf, bool)>> {
+        if !config.roots.is_empty() {
+            let mut hits = Vec::new();
+            for root in &config.roots {
+                let root_hits = self.list_noise(&config.for_root(root))?;
+                hits.extend(
+                    root_hits
+                        .into_iter()
+                        .map(|(path, is_dir)| (Path::new(&root.label).join(path), is_dir)),
+                );
+            }
+            return Ok(hits);
+        }
 
-        // Exclude wins over include
-        if !config.exclude_paths.is_empty() {
-            for exclude in &config.exclude_paths {
-                if path_str.contains(exclude) {
-                    return false;
-                }
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&hits);
+        let root = config.root_path.clone();
+        let walker = Self::walker(config, move |entry, is_dir| {
+            let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+            if let Ok(mut hits) = sink.lock() {
+                hits.push((relative.to_path_buf(), is_dir));
+            }
+        })?;
+        for result in walker {
+            if let Err(err) = result {
+                warn!("Skipping file due to error: {}", err);
             }
         }
 
-        if !config.include_paths.is_empty() {
-            let mut matched = false;
-            for include in &config.include_paths {
-                if path_str.contains(include) {
-                    matched = true;
-                    break;
-                }
+        let mut hits = std::mem::take(&mut *hits.lock().map_err(|e| anyhow!("{}", e))?);
+        hits.sort();
+        Ok(hits)
+    }
+
+    /// Compiles the `--ignore-file` rules. Relative files are looked up in the
+    /// project root (a root lacking one skips it), and their patterns are
+    /// relative to the project root.
+    fn build_ignore_files(config: &ContextConfig) -> Result<Gitignore> {
+        let mut builder = GitignoreBuilder::new(&config.root_path);
+        for file in &config.ignore_files {
+            let path = config.root_path.join(file);
+            if file.is_relative() && !path.exists() {
+                debug!("Ignore file {:?} not found in this root", path);
+                continue;
             }
-            if !matched {
-                return false;
+            if let Some(err) = builder.add(&path) {
+                return Err(anyhow!("Invalid ignore file {:?}: {}", path, err));
             }
         }
+        Ok(builder.build()?)
+    }
+
+    /// Records where each file really lives and keeps a single entry per real
+    /// file, preferring the path that does not go through a link.
+    fn resolve_links(root: &Path, files: Vec<FileNode>) -> Vec<FileNode> {
+        let real_root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let mut resolved: Vec<(PathBuf, FileNode)> = files
+            .into_iter()
+            .map(|mut node| {
+                let real = fs::canonicalize(&node.path).unwrap_or_else(|_| node.path.clone());
+                let target = real.strip_prefix(&real_root).unwrap_or(&real);
+                if target != node.relative_path {
+                    node.link_target = Some(target.to_path_buf());
+                }
+                (real, node)
+            })
+            .collect();
+        resolved.sort_by(|(_, a), (_, b)| {
+            (a.link_target.is_some(), &a.relative_path)
+                .cmp(&(b.link_target.is_some(), &b.relative_path))
+        });
+
+        let mut seen = HashSet::new();
+        resolved
+            .into_iter()
+            .filter(|(real, node)| {
+                let first = seen.insert(real.clone());
+                if !first {
+                    debug!(
+                        "Skipping {:?}: already reached as {:?}",
+                        node.relative_path, real
+                    );
+                }
+                first
+            })
+            .map(|(_, node)| node)
+            .collect()
+    }
 
-        let ext = path
+    /// Keeps the changed files plus the related files asked for.
+    fn select_changed(
+        files: Vec<FileNode>,
+        changed: &HashSet<PathBuf>,
+        selection: &GitSelection,
+    ) -> Vec<FileNode> {
+        let sibling_dirs: HashSet<&Path> = if selection.related.contains(&RelatedFiles::Siblings) {
+            changed.iter().filter_map(|path| path.parent()).collect()
+        } else {
+            HashSet::new()
+        };
+        let modules: HashSet<String> = if selection.related.contains(&RelatedFiles::Importers) {
+            changed
+                .iter()
+                .filter_map(|path| module_name(path))
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        files
+            .into_par_iter()
+            .filter(|node| {
+                changed.contains(&node.relative_path)
+                    || node
+                        .relative_path
+                        .parent()
+                        .is_some_and(|dir| sibling_dirs.contains(dir))
+                    || (!modules.is_empty()
+                        && fs::read_to_string(&node.path)
+                            .is_ok_and(|text| imports_any(&text, &modules)))
+            })
+            .collect()
+    }
+
+    /// Records the modification time of each file and keeps those within the
+    /// time filter. With `--git-mtime`, files never committed fall back to
+    /// their file system time.
+    fn select_modified(
+        root: &Path,
+        files: Vec<FileNode>,
+        time: &TimeFilter,
+    ) -> Result<Vec<FileNode>> {
+        let commits = if time.git_mtime {
+            let wanted = files
+                .iter()
+                .map(|node| node.relative_path.clone())
+                .collect();
+            git::last_commit_times(root, &wanted)?
+        } else {
+            HashMap::new()
+        };
+
+        Ok(files
+            .into_par_iter()
+            .filter_map(|mut node| {
+                let modified = match commits.get(&node.relative_path) {
+                    Some(&time) => time,
+                    None => file_mtime(&node.path)?,
+                };
+                node.modified = Some(modified);
+                time.matches(modified).then_some(node)
+            })
+            .collect())
+    }
+
+    /// Checks filters: Extensions and Paths.
+    ///
+    /// Path globs are matched against the path relative to the project root.
+    pub(crate) fn matches_filters(
+        relative_path: &Path,
+        include: &PathMatcher,
+        exclude: &PathMatcher,
+        config: &ContextConfig,
+    ) -> bool {
+        // Exclude wins over include
+        if exclude.is_match(relative_path) {
+            return false;
+        }
+
+        if !include.is_empty() && !include.is_match(relative_path) {
+            return false;
+        }
+
+        let ext = relative_path
             .extension()
             .and_then(|e| e.to_str())
             .unwrap_or("")
@@ -103,64 +259,97 @@ impl FsScanner {
 }
 
 impl ProjectScanner for FsScanner {
+    /// Scans every root; with several, each file is moved under its root label.
     fn scan(&self, config: &ContextConfig) -> Result<Vec<FileNode>> {
+        if config.roots.is_empty() {
+            return self.scan_root(config);
+        }
+        let mut files = Vec::new();
+        for root in &config.roots {
+            let nodes = self.scan_root(&config.for_root(root))?;
+            files.extend(nodes.into_iter().map(|node| node.under_root(&root.label)));
+        }
+        Ok(files)
+    }
+}
+
+impl FsScanner {
+    fn scan_root(&self, config: &ContextConfig) -> Result<Vec<FileNode>> {
         let root = &config.root_path;
         debug!(
             "Starting scan at: {:?}. NoIgnore: {}, Hidden: {}",
             root, config.no_ignore, config.include_hidden
         );
 
-        let mut builder = WalkBuilder::new(root);
-
-        builder
-            .standard_filters(true)
-            .hidden(!config.include_hidden)
-            .git_ignore(!config.no_ignore)
-            .git_global(!config.no_ignore)
-            .ignore(!config.no_ignore);
-
-        if let Some(depth) = config.max_depth {
-            builder.max_depth(Some(depth));
-        }
+        let walker = Self::walker(config, |_, _| {})?;
 
-        builder.filter_entry(|entry| !Self::is_noise(entry));
+        let include = PathMatcher::new(&config.include_paths)?;
+        let exclude = PathMatcher::new(&config.exclude_paths)?;
 
         let mut files = Vec::new();
 
-        for result in builder.build() {
+        for result in walker {
             match result {
                 Ok(entry) => {
                     if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
                         continue;
                     }
 
-                    let path = entry.path();
-
-                    if !Self::matches_filters(path, config) {
-                        continue;
-                    }
-
-                    let path_buf = path.to_path_buf();
+                    let path_buf = entry.path().to_path_buf();
                     let relative_path = match path_buf.strip_prefix(root) {
                         Ok(p) => p.to_path_buf(),
                         Err(_) => path_buf.clone(),
                     };
 
-                    files.push(FileNode::new(path_buf, relative_path));
+                    if !Self::matches_filters(&relative_path, &include, &exclude, config) {
+                        continue;
+                    }
+
+                    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    files.push(
+                        FileNode::new(path_buf, relative_path).with_metadata(size, FileKind::Text),
+                    );
                 }
                 Err(err) => {
                     warn!("Skipping file due to error: {}", err);
                 }
             }
         }
+        if config.follow_symlinks {
+            files = Self::resolve_links(root, files);
+        }
+        if config.git.is_active() {
+            let changed = git::changed_files(root, &config.git)?;
+            debug!("Git selection: {} changed files.", changed.len());
+            files = Self::select_changed(files, &changed, &config.git);
+        }
+        if config.time.is_active() {
+            files = Self::select_modified(root, files, &config.time)?;
+            debug!("Time filter: {} files kept.", files.len());
+        }
         files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
 
+        // Classify from the first few KB only; unreadable files stay `Text` and
+        // report their error when read.
+        files.par_iter_mut().for_each(|node| {
+            if let Ok(kind) = sniff_file(&node.path) {
+                node.kind = kind;
+            }
+        });
+
         debug!("Scan complete. Found {} files.", files.len());
         Ok(files)
     }
 }
 
+/// File system modification time, to the second.
+fn file_mtime(path: &Path) -> Option<DateTime<Utc>> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    DateTime::from_timestamp(DateTime::<Utc>::from(modified).timestamp(), 0)
+}
+
 #[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
 mod tests {
     use super::*;
     use std::fs::{self, File};
@@ -224,6 +413,57 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_scan_glob_filters_use_relative_paths() -> Result<()> {
+        let dir = tempdir()?;
+        // The project lives under a directory named "src" and contains "latest.rs":
+        // neither may confuse the filters.
+        let root = dir.path().join("src").join("project");
+
+        fs::create_dir_all(root.join("src/generated"))?;
+        fs::create_dir_all(root.join("types"))?;
+        File::create(root.join("src/lib.rs"))?;
+        File::create(root.join("src/latest.rs"))?;
+        File::create(root.join("src/generated/api.rs"))?;
+        File::create(root.join("types/index.d.ts"))?;
+        File::create(root.join("types/index.ts"))?;
+        File::create(root.join("Dockerfile"))?;
+        File::create(root.join("notes.md"))?;
+
+        let scan = |include: &[&str], exclude: &[&str]| -> Result<Vec<String>> {
+            let config = ContextConfig {
+                root_path: root.clone(),
+                include_paths: include.iter().map(|p| p.to_string()).collect(),
+                exclude_paths: exclude.iter().map(|p| p.to_string()).collect(),
+                ..Default::default()
+            };
+            Ok(FsScanner::new()
+                .scan(&config)?
+                .iter()
+                .map(|f| f.relative_path.to_string_lossy().replace('\\', "/"))
+                .collect())
+        };
+
+        assert_eq!(
+            scan(
+                &["src/**/*.rs", "!src/generated/**", "Dockerfile", "*.d.ts"],
+                &[]
+            )?,
+            [
+                "Dockerfile",
+                "src/latest.rs",
+                "src/lib.rs",
+                "types/index.d.ts"
+            ]
+        );
+        assert_eq!(
+            scan(&[], &["test", "src/", "*.md"])?,
+            ["Dockerfile", "types/index.d.ts", "types/index.ts"]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_scan_gitignore_respect_and_bypass() -> Result<()> {
         let dir = tempdir()?;
@@ -289,4 +529,147 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_scan_context_ignore_files() -> Result<()> {
+        let dir = tempdir()?;
+        let root = dir.path().join("project");
+
+        fs::create_dir_all(root.join("pkg/fixtures"))?;
+        File::create(root.join("main.rs"))?;
+        File::create(root.join("secrets.txt"))?;
+        File::create(root.join("pkg/lib.rs"))?;
+        File::create(root.join("pkg/fixtures/big.json"))?;
+        File::create(root.join("pkg/notes.md"))?;
+        fs::write(root.join(CONTEXT_IGNORE_FILE), "secrets.txt\n")?;
+        fs::write(root.join("pkg").join(CONTEXT_IGNORE_FILE), "fixtures/\n")?;
+        let extra = dir.path().join("extra.ignore");
+        fs::write(&extra, "pkg/*.md\n")?;
+
+        let config = ContextConfig {
+            root_path: root.clone(),
+            no_ignore: true,
+            ignore_files: vec![extra],
+            ..Default::default()
+        };
+        let paths: Vec<String> = FsScanner::new()
+            .scan(&config)?
+            .iter()
+            .map(|f| f.relative_path.to_string_lossy().replace('\\', "/"))
+            .collect();
+
+        assert_eq!(paths, ["main.rs", "pkg/lib.rs"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_relative_ignore_file() -> Result<()> {
+        let dir = tempdir()?;
+        let root = dir.path().join("project");
+
+        fs::create_dir_all(&root)?;
+        File::create(root.join("main.rs"))?;
+        File::create(root.join("notes.md"))?;
+        fs::write(root.join("extra.ignore"), "*.md\nextra.ignore\n")?;
+
+        let config = ContextConfig {
+            root_path: root.clone(),
+            ignore_files: vec![PathBuf::from("extra.ignore")],
+            ..Default::default()
+        };
+        let paths: Vec<String> = FsScanner::new()
+            .scan(&config)?
+            .iter()
+            .map(|f| f.relative_path.to_string_lossy().replace('\\', "/"))
+            .collect();
+
+        assert_eq!(paths, ["main.rs"]);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_follow_symlinks() -> Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempdir()?;
+        let root = dir.path().join("project");
+        let shared = dir.path().join("shared");
+        fs::create_dir_all(root.join("app"))?;
+        fs::create_dir_all(&shared)?;
+        fs::write(root.join("app/main.rs"), "fn main() {}")?;
+        fs::write(shared.join("util.rs"), "pub fn util() {}")?;
+        // Two links to the same outside directory, one to an inside file and a loop.
+        symlink(&shared, root.join("shared"))?;
+        symlink(&shared, root.join("app/shared"))?;
+        symlink(root.join("app/main.rs"), root.join("entry.rs"))?;
+        symlink(&root, root.join("app/loop"))?;
+
+        let scan = |follow_symlinks| -> Result<Vec<(String, Option<PathBuf>)>> {
+            let config = ContextConfig {
+                root_path: root.clone(),
+                follow_symlinks,
+                ..Default::default()
+            };
+            Ok(FsScanner::new()
+                .scan(&config)?
+                .into_iter()
+                .map(|f| {
+                    (
+                        f.relative_path.to_string_lossy().into_owned(),
+                        f.link_target,
+                    )
+                })
+                .collect())
+        };
+
+        assert_eq!(scan(false)?, [("app/main.rs".to_string(), None)]);
+        assert_eq!(
+            scan(true)?,
+            [
+                ("app/main.rs".to_string(), None),
+                (
+                    "app/shared/util.rs".to_string(),
+                    Some(fs::canonicalize(shared.join("util.rs"))?)
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_time_filter() -> Result<()> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        let now = Utc::now();
+        for (name, days_ago) in [("old.rs", 30), ("recent.rs", 2), ("today.rs", 0)] {
+            let file = File::create(root.join(name))?;
+            let modified = now - chrono::Duration::days(days_ago);
+            file.set_modified(modified.into())?;
+        }
+
+        let config = ContextConfig {
+            root_path: root.to_path_buf(),
+            time: TimeFilter {
+                newer_than: Some(now - chrono::Duration::days(7)),
+                older_than: Some(now - chrono::Duration::days(1)),
+                git_mtime: false,
+            },
+            ..Default::default()
+        };
+        let files = FsScanner::new().scan(&config)?;
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].relative_path, PathBuf::from("recent.rs"));
+        let modified = files[0].modified.expect("mtime recorded");
+        assert_eq!(
+            modified.timestamp(),
+            (now - chrono::Duration::days(2)).timestamp()
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,344 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use git2::{Delta, Diff, DiffOptions, Oid, Patch, Repository};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::config::GitSelection;
+
+/// Paths, relative to `root`, of the files touched according to `selection`.
+///
+/// Deleted files are left out, as there is nothing left to read. Files outside
+/// `root` (when it is a subdirectory of the repository) are ignored.
+pub fn changed_files(root: &Path, selection: &GitSelection) -> Result<HashSet<PathBuf>> {
+    let (repo, workdir, root) = open(root)?;
+
+    // An unborn HEAD (no commits yet) compares against the empty tree.
+    let head = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+
+    let mut diffs: Vec<Diff> = Vec::new();
+    if selection.staged {
+        diffs.push(repo.diff_tree_to_index(head.as_ref(), None, None)?);
+    }
+    if selection.uncommitted {
+        diffs.push(
+            repo.diff_tree_to_workdir_with_index(head.as_ref(), Some(&mut with_untracked()))?,
+        );
+    }
+    if let Some(rev) = &selection.changed_since {
+        let tree = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_tree())
+            .with_context(|| format!("Unknown git revision `{}`", rev))?;
+        diffs.push(repo.diff_tree_to_workdir_with_index(Some(&tree), Some(&mut with_untracked()))?);
+    }
+    if let Some(range) = &selection.diff_range {
+        diffs.push(diff_range(&repo, range, &mut with_untracked())?);
+    }
+
+    let mut paths = HashSet::new();
+    for delta in diffs.iter().flat_map(|diff| diff.deltas()) {
+        if delta.status() == Delta::Deleted {
+            continue;
+        }
+        if let Some(relative) = relative_to_root(delta.new_file().path(), &workdir, &root) {
+            paths.insert(relative);
+        }
+    }
+    Ok(paths)
+}
+
+/// Unified diff of every file changed in `range`, keyed by path relative to
+/// `root`, with `context_lines` unchanged lines around each hunk.
+pub fn file_diffs(
+    root: &Path,
+    range: &str,
+    context_lines: u32,
+) -> Result<HashMap<PathBuf, String>> {
+    let (repo, workdir, root) = open(root)?;
+    let mut options = with_untracked();
+    options
+        .show_untracked_content(true)
+        .context_lines(context_lines);
+    let diff = diff_range(&repo, range, &mut options)?;
+
+    let mut patches = HashMap::new();
+    for (index, delta) in diff.deltas().enumerate() {
+        let Some(relative) = relative_to_root(delta.new_file().path(), &workdir, &root) else {
+            continue;
+        };
+        if let Some(mut patch) = Patch::from_diff(&diff, index)? {
+            let text = String::from_utf8_lossy(&patch.to_buf()?).into_owned();
+            patches.insert(relative, text);
+        }
+    }
+    Ok(patches)
+}
+
+/// Time of the last commit touching each of `wanted` (paths relative to
+/// `root`), following first parents from `HEAD`. Files never committed are
+/// left out.
+pub fn last_commit_times(
+    root: &Path,
+    wanted: &HashSet<PathBuf>,
+) -> Result<HashMap<PathBuf, DateTime<Utc>>> {
+    let (repo, workdir, root) = open(root)?;
+    let mut times = HashMap::new();
+    let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) else {
+        return Ok(times);
+    };
+
+    // Only files in HEAD's tree can be found (a staged-only file never shows
+    // up in the history); stop walking once all of them are.
+    let head_tree = head.tree()?;
+    let prefix = root.strip_prefix(&workdir)?;
+    let committed = wanted
+        .iter()
+        .filter(|path| head_tree.get_path(&prefix.join(path)).is_ok())
+        .count();
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head.id())?;
+    revwalk.simplify_first_parent()?;
+    for oid in revwalk {
+        if times.len() >= committed {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        let parent = commit
+            .parent(0)
+            .ok()
+            .map(|parent| parent.tree())
+            .transpose()?;
+        let diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), None)?;
+        let time = DateTime::from_timestamp(commit.time().seconds(), 0).unwrap_or_default();
+        for delta in diff.deltas() {
+            if let Some(relative) = relative_to_root(delta.new_file().path(), &workdir, &root) {
+                if wanted.contains(&relative) {
+                    times.entry(relative).or_insert(time);
+                }
+            }
+        }
+    }
+    Ok(times)
+}
+
+/// Opens the repository containing `root` and resolves the tree of `root`
+/// (which may be a subdirectory of the work tree) at revision `rev`.
+pub fn revision_tree(root: &Path, rev: &str) -> Result<(Repository, Oid)> {
+    let (repo, workdir, root) = open(root)?;
+    let prefix = root.strip_prefix(&workdir)?.to_path_buf();
+    let oid = {
+        let tree = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_tree())
+            .with_context(|| format!("Unknown git revision `{}`", rev))?;
+        if prefix.as_os_str().is_empty() {
+            tree.id()
+        } else {
+            tree.get_path(&prefix)
+                .with_context(|| format!("{} does not exist at `{}`", prefix.display(), rev))?
+                .id()
+        }
+    };
+    Ok((repo, oid))
+}
+
+/// Opens the repository containing `root`; returns it with its canonical work
+/// tree and the canonical root.
+fn open(root: &Path) -> Result<(Repository, PathBuf, PathBuf)> {
+    let repo = Repository::discover(root)
+        .with_context(|| format!("{} is not inside a git repository", root.display()))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("Bare git repositories are not supported"))?;
+    let workdir = fs::canonicalize(workdir)?;
+    Ok((repo, workdir, fs::canonicalize(root)?))
+}
+
+/// Diff of `A..B` (tree to tree), `A...B` (from the merge base of both) or `A`
+/// (against the working tree, untracked files included when `options` asks).
+fn diff_range<'r>(
+    repo: &'r Repository,
+    range: &str,
+    options: &mut DiffOptions,
+) -> Result<Diff<'r>> {
+    let tree = |rev: &str| {
+        repo.revparse_single(rev)
+            .and_then(|object| object.peel_to_tree())
+            .with_context(|| format!("Unknown git revision `{}`", rev))
+    };
+    let (from, to) = match range.split_once("...") {
+        Some((a, b)) => {
+            let a = repo.revparse_single(a)?.peel_to_commit()?.id();
+            let b_rev = if b.is_empty() { "HEAD" } else { b };
+            let b_id = repo.revparse_single(b_rev)?.peel_to_commit()?.id();
+            let base = repo.merge_base(a, b_id)?;
+            (
+                repo.find_commit(base)?.tree()?,
+                (!b.is_empty()).then_some(b),
+            )
+        }
+        None => match range.split_once("..") {
+            Some((a, b)) => (tree(a)?, (!b.is_empty()).then_some(b)),
+            None => (tree(range)?, None),
+        },
+    };
+    let diff = match to {
+        Some(to) => repo.diff_tree_to_tree(Some(&from), Some(&tree(to)?), Some(options))?,
+        None => repo.diff_tree_to_workdir_with_index(Some(&from), Some(options))?,
+    };
+    Ok(diff)
+}
+
+fn relative_to_root(path: Option<&Path>, workdir: &Path, root: &Path) -> Option<PathBuf> {
+    let absolute = workdir.join(path?);
+    absolute.strip_prefix(root).ok().map(Path::to_path_buf)
+}
+
+fn with_untracked() -> DiffOptions {
+    let mut options = DiffOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    options
+}
+
+/// Commits the whole work tree of a test repository.
+#[cfg(test)]
+pub(crate) fn commit_all(repo: &Repository, message: &str) -> Result<()> {
+    let mut index = repo.index()?;
+    index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("test", "test@example.com")?;
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_changed_files_selection() -> Result<()> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        let repo = Repository::init(root)?;
+        fs::create_dir(root.join("src"))?;
+        fs::write(root.join("src/a.rs"), "a")?;
+        fs::write(root.join("src/b.rs"), "b")?;
+        fs::write(root.join("gone.rs"), "gone")?;
+        commit_all(&repo, "base")?;
+        let base = repo.head()?.peel_to_commit()?.id().to_string();
+
+        fs::write(root.join("src/a.rs"), "a2")?;
+        fs::remove_file(root.join("gone.rs"))?;
+        commit_all(&repo, "change a")?;
+
+        fs::write(root.join("src/b.rs"), "b2")?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new("src/b.rs"))?;
+        index.write()?;
+        fs::write(root.join("notes.md"), "untracked")?;
+
+        let select = |selection: GitSelection| -> Result<Vec<String>> {
+            let mut paths: Vec<String> = changed_files(root, &selection)?
+                .into_iter()
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .collect();
+            paths.sort();
+            Ok(paths)
+        };
+
+        let staged = GitSelection {
+            staged: true,
+            ..Default::default()
+        };
+        assert_eq!(select(staged)?, ["src/b.rs"]);
+
+        let uncommitted = GitSelection {
+            uncommitted: true,
+            ..Default::default()
+        };
+        assert_eq!(select(uncommitted)?, ["notes.md", "src/b.rs"]);
+
+        let since = GitSelection {
+            changed_since: Some(base),
+            ..Default::default()
+        };
+        assert_eq!(select(since)?, ["notes.md", "src/a.rs", "src/b.rs"]);
+
+        // A subdirectory root only sees its own files, relative to itself.
+        let sub = GitSelection {
+            changed_since: Some("HEAD~1".into()),
+            ..Default::default()
+        };
+        let paths: Vec<_> = changed_files(&root.join("src"), &sub)?
+            .into_iter()
+            .collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&PathBuf::from("a.rs")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_commit_times_skips_staged_files() -> Result<()> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        let repo = Repository::init(root)?;
+        fs::write(root.join("old.rs"), "old")?;
+        commit_all(&repo, "old")?;
+        fs::write(root.join("new.rs"), "new")?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new("new.rs"))?;
+        index.write()?;
+
+        let wanted: HashSet<PathBuf> = ["old.rs", "new.rs"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        let times = last_commit_times(root, &wanted)?;
+
+        assert_eq!(times.len(), 1);
+        assert!(times.contains_key(Path::new("old.rs")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_diffs_range() -> Result<()> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        let repo = Repository::init(root)?;
+        let lines: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+        fs::write(root.join("a.txt"), &lines)?;
+        commit_all(&repo, "base")?;
+        fs::write(root.join("a.txt"), lines.replace("line 5\n", "five\n"))?;
+        commit_all(&repo, "edit")?;
+        fs::write(root.join("new.txt"), "fresh\n")?;
+
+        let committed = file_diffs(root, "HEAD~1..HEAD", 1)?;
+        assert_eq!(committed.len(), 1);
+        let patch = &committed[Path::new("a.txt")];
+        assert!(patch.contains("@@ -4,3 +4,3 @@"));
+        assert!(patch.ends_with("\n line 4\n-line 5\n+five\n line 6\n"));
+
+        let with_worktree = file_diffs(root, "HEAD~1", 0)?;
+        assert!(with_worktree[Path::new("new.txt")].contains("+fresh\n"));
+        let patch = &with_worktree[Path::new("a.txt")];
+        assert!(patch.contains("@@ -5 +5 @@") && patch.ends_with("\n-line 5\n+five\n"));
+
+        Ok(())
+    }
+}
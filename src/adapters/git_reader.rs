@@ -0,0 +1,91 @@
+use anyhow::{anyhow, Result};
+use git2::{Oid, Repository};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::adapters::fs_reader::FsReader;
+use crate::adapters::git::revision_tree;
+use crate::core::content::{ContentType, FileContext};
+use crate::core::file::FileNode;
+use crate::ports::reader::FileReader;
+
+/// Reads file contents from the blobs of a git revision (`--rev`), then hands
+/// them to the same parsers as `FsReader`.
+pub struct GitReader {
+    /// `Repository` is not `Sync`; blobs are loaded one at a time and parsed
+    /// outside the lock.
+    repo: Mutex<Repository>,
+    tree: Oid,
+    reader: FsReader,
+}
+
+impl GitReader {
+    pub fn open(root: &Path, rev: &str, reader: FsReader) -> Result<Self> {
+        let (repo, tree) = revision_tree(root, rev)?;
+        Ok(Self {
+            repo: Mutex::new(repo),
+            tree,
+            reader,
+        })
+    }
+
+    fn blob(&self, relative_path: &Path) -> Result<Vec<u8>> {
+        let repo = self.repo.lock().map_err(|e| anyhow!("{}", e))?;
+        let entry = repo.find_tree(self.tree)?.get_path(relative_path)?;
+        let blob = repo.find_blob(entry.id())?;
+        Ok(blob.content().to_vec())
+    }
+}
+
+impl FileReader for GitReader {
+    fn read_file(&self, node: &FileNode) -> FileContext {
+        match self.blob(&node.relative_path) {
+            Ok(bytes) => self.reader.read_bytes(node, &bytes),
+            Err(e) => {
+                let mut context = FileContext::new(
+                    node.path.clone(),
+                    node.relative_path.clone(),
+                    ContentType::Error(e.to_string()),
+                    self.reader.detect_language(&node.path),
+                    0,
+                );
+                context.root = node.root.clone();
+                context
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::git::commit_all;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_blob_at_revision() -> Result<()> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        let repo = Repository::init(root)?;
+        fs::write(root.join("lib.rs"), "pub fn v1() {}\n")?;
+        commit_all(&repo, "v1")?;
+        fs::write(root.join("lib.rs"), "pub fn v2() {}\n")?;
+
+        let reader = GitReader::open(root, "HEAD", FsReader::new())?;
+        let node = FileNode::new(root.join("lib.rs"), PathBuf::from("lib.rs"));
+        assert_eq!(
+            reader.read_file(&node).content,
+            ContentType::Text("pub fn v1() {}\n".into())
+        );
+
+        let missing = FileNode::new(root.join("gone.rs"), PathBuf::from("gone.rs"));
+        assert!(matches!(
+            reader.read_file(&missing).content,
+            ContentType::Error(_)
+        ));
+
+        Ok(())
+    }
+}
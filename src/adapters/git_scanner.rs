@@ -0,0 +1,143 @@
+use anyhow::Result;
+use git2::{ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+use std::path::Path;
+use tracing::{debug, warn};
+
+use crate::adapters::fs_scanner::FsScanner;
+use crate::adapters::git::revision_tree;
+use crate::adapters::sniff::sniff_contents;
+use crate::core::config::ContextConfig;
+use crate::core::file::FileNode;
+use crate::core::pattern::PathMatcher;
+use crate::ports::scanner::ProjectScanner;
+
+/// Git mode of symbolic link entries.
+const SYMLINK_MODE: i32 = 0o120000;
+
+/// Lists the files of a git revision instead of the working tree (`--rev`).
+///
+/// The noise rules, hidden-file, depth and path filters apply as on disk;
+/// ignore files do not, since committed files are tracked by definition.
+pub struct GitScanner {
+    repo: Repository,
+    tree: Oid,
+}
+
+impl GitScanner {
+    pub fn open(root: &Path, rev: &str) -> Result<Self> {
+        let (repo, tree) = revision_tree(root, rev)?;
+        Ok(Self { repo, tree })
+    }
+}
+
+impl ProjectScanner for GitScanner {
+    fn scan(&self, config: &ContextConfig) -> Result<Vec<FileNode>> {
+        debug!(
+            "Starting scan of git tree {} for {:?}",
+            self.tree, config.root_path
+        );
+        let tree = self.repo.find_tree(self.tree)?;
+        let include = PathMatcher::new(&config.include_paths)?;
+        let exclude = PathMatcher::new(&config.exclude_paths)?;
+
+        let mut files = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            let Some(name) = entry.name() else {
+                return TreeWalkResult::Skip;
+            };
+            let is_dir = entry.kind() == Some(ObjectType::Tree);
+            if (!config.include_hidden && name.starts_with('.'))
+                || config.noise.is_noise(name, is_dir)
+            {
+                return TreeWalkResult::Skip;
+            }
+
+            let relative_path = Path::new(dir).join(name);
+            if is_dir {
+                let depth = relative_path.components().count();
+                return match config.max_depth {
+                    Some(max) if depth >= max => TreeWalkResult::Skip,
+                    _ => TreeWalkResult::Ok,
+                };
+            }
+            // Submodules and symlinks have no content of their own.
+            if entry.kind() != Some(ObjectType::Blob) || entry.filemode() == SYMLINK_MODE {
+                return TreeWalkResult::Ok;
+            }
+            if !FsScanner::matches_filters(&relative_path, &include, &exclude, config) {
+                return TreeWalkResult::Ok;
+            }
+
+            match self.repo.find_blob(entry.id()) {
+                Ok(blob) => {
+                    let kind = sniff_contents(&relative_path, blob.content());
+                    files.push(
+                        FileNode::new(config.root_path.join(&relative_path), relative_path)
+                            .with_metadata(blob.size() as u64, kind),
+                    );
+                }
+                Err(err) => warn!("Skipping {:?} due to error: {}", relative_path, err),
+            }
+            TreeWalkResult::Ok
+        })?;
+        files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+        debug!("Scan complete. Found {} files.", files.len());
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::git::commit_all;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_scan_revision_tree() -> Result<()> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        let repo = Repository::init(root)?;
+        fs::create_dir_all(root.join("src/deep/er"))?;
+        fs::create_dir_all(root.join("node_modules/pkg"))?;
+        fs::write(root.join("src/lib.rs"), "pub fn v1() {}")?;
+        fs::write(root.join("src/deep/er/x.rs"), "")?;
+        fs::write(root.join("node_modules/pkg/index.js"), "")?;
+        fs::write(root.join(".env.example"), "")?;
+        commit_all(&repo, "v1")?;
+        repo.tag_lightweight("v1", &repo.head()?.peel(ObjectType::Commit)?, false)?;
+
+        fs::write(root.join("src/new.rs"), "")?;
+        fs::remove_file(root.join("src/deep/er/x.rs"))?;
+        commit_all(&repo, "v2")?;
+
+        let scan = |max_depth| -> Result<Vec<String>> {
+            let config = ContextConfig {
+                root_path: root.to_path_buf(),
+                max_depth,
+                ..Default::default()
+            };
+            Ok(GitScanner::open(root, "v1")?
+                .scan(&config)?
+                .iter()
+                .map(|f| f.relative_path.to_string_lossy().replace('\\', "/"))
+                .collect())
+        };
+
+        assert_eq!(scan(None)?, ["src/deep/er/x.rs", "src/lib.rs"]);
+        assert_eq!(scan(Some(2))?, ["src/lib.rs"]);
+
+        // A subdirectory root sees its own subtree, relative to itself.
+        let config = ContextConfig {
+            root_path: root.join("src"),
+            ..Default::default()
+        };
+        let files = GitScanner::open(&root.join("src"), "HEAD")?.scan(&config)?;
+        let paths: Vec<_> = files.iter().map(|f| f.relative_path.clone()).collect();
+        assert_eq!(paths, [Path::new("lib.rs"), Path::new("new.rs")]);
+        assert_eq!(files[0].size, 14);
+
+        Ok(())
+    }
+}
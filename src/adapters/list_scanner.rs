@@ -0,0 +1,136 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+use crate::adapters::sniff::sniff_file;
+use crate::core::config::ContextConfig;
+use crate::core::file::{parse_path_spec, FileKind, FileNode, Fragment};
+use crate::ports::scanner::ProjectScanner;
+
+/// Scans an explicit list of files (`--files-from`), such as the output of
+/// `rg -l`, `git ls-files` or `fd`.
+///
+/// The list is taken as is: no ignore, noise or path filter applies, and the
+/// order of the list is kept.
+pub struct ListScanner {
+    entries: Vec<(PathBuf, Option<Fragment>)>,
+}
+
+impl ListScanner {
+    pub fn new(entries: Vec<(PathBuf, Option<Fragment>)>) -> Self {
+        Self { entries }
+    }
+
+    /// Reads NUL-separated entries when the input holds a NUL byte (`-print0`,
+    /// `-z`), newline-separated ones otherwise. Each entry may end in `:START-END`
+    /// or `#symbol`.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        let separator = if input.contains('\0') { '\0' } else { '\n' };
+        let entries = input
+            .split(separator)
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.trim().is_empty())
+            .map(parse_path_spec)
+            .collect();
+        Ok(Self::new(entries))
+    }
+}
+
+impl ProjectScanner for ListScanner {
+    /// Paths in the list are relative to the current directory, like the output
+    /// of the tools above; inside the root they are reported relative to it.
+    fn scan(&self, config: &ContextConfig) -> Result<Vec<FileNode>> {
+        let root = fs::canonicalize(&config.root_path)?;
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+
+        for (path, fragment) in &self.entries {
+            let metadata = match fs::metadata(path) {
+                Ok(metadata) if metadata.is_file() => metadata,
+                Ok(_) => {
+                    warn!("Skipping {:?}: not a file", path);
+                    continue;
+                }
+                Err(err) => {
+                    warn!("Skipping {:?}: {}", path, err);
+                    continue;
+                }
+            };
+            let real = fs::canonicalize(path)?;
+            if !seen.insert((real.clone(), fragment.clone())) {
+                continue;
+            }
+
+            // Like the file system scan, paths inside the root start with it
+            // as given rather than with its canonical form.
+            let (path, relative_path) = match real.strip_prefix(&root) {
+                Ok(relative) => (config.root_path.join(relative), relative.to_path_buf()),
+                Err(_) => (path.clone(), path.clone()),
+            };
+            let kind = sniff_file(&real).unwrap_or(FileKind::Text);
+            let mut node = FileNode::new(path, relative_path).with_metadata(metadata.len(), kind);
+            node.fragment = fragment.clone();
+            files.push(node);
+        }
+
+        debug!(
+            "File list: {} of {} entries found.",
+            files.len(),
+            self.entries.len()
+        );
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::file::LineRange;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_list_scanner_entries() -> Result<()> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        fs::create_dir(root.join("src"))?;
+        fs::write(root.join("src/a.rs"), "a")?;
+        fs::write(root.join("b.rs"), "b")?;
+
+        let a = root.join("src/a.rs").to_string_lossy().into_owned();
+        let b = root.join("b.rs").to_string_lossy().into_owned();
+        let missing = root.join("missing.rs").to_string_lossy().into_owned();
+
+        let newline = format!("{b}\n{a}:2-4\r\n{missing}\n\n{b}\n{b}#run\n");
+        let nul = format!("{b}\0{a}:2-4\0{b}#run\0");
+        for input in [newline, nul] {
+            let config = ContextConfig {
+                root_path: root.join("src").join(".."),
+                ..Default::default()
+            };
+            let files = ListScanner::from_reader(input.as_bytes())?.scan(&config)?;
+            assert_eq!(files[0].path, config.root_path.join("b.rs"));
+            let found: Vec<_> = files
+                .iter()
+                .map(|f| (f.relative_path.clone(), f.fragment.clone()))
+                .collect();
+            assert_eq!(
+                found,
+                [
+                    (PathBuf::from("b.rs"), None),
+                    (
+                        PathBuf::from("src").join("a.rs"),
+                        Some(Fragment::Lines(LineRange { start: 2, end: 4 }))
+                    ),
+                    (PathBuf::from("b.rs"), Some(Fragment::Symbol("run".into()))),
+                ]
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -2,5 +2,12 @@
 
 pub mod fs_reader;
 pub mod fs_scanner;
+pub mod git;
+pub mod git_reader;
+pub mod git_scanner;
+pub mod list_scanner;
 pub mod output;
 pub mod parsers;
+pub mod project_config;
+pub mod sniff;
+pub mod tokenizer;
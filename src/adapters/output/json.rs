@@ -5,28 +5,45 @@ use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::Path;
 
-use crate::core::config::ContextConfig;
-use crate::core::content::{minify_content, ContentType, FileContext};
+use crate::core::config::{ContextConfig, ProjectRoot};
+use crate::core::content::{render_text, ContentType, FileContext};
+use crate::core::fidelity::{tier_stats, TierStats};
+use crate::core::report::{DuplicateStats, OmittedFile, PartInfo, ReportMeta, SimilarStats};
 use crate::ports::writer::ContextWriter;
 
 #[derive(Serialize)]
 struct JsonReport<'a> {
-    metadata: JsonMetadata,
+    metadata: JsonMetadata<'a>,
     files: &'a [FileContext],
 }
 
 #[derive(Serialize)]
-struct JsonMetadata {
+struct JsonMetadata<'a> {
     project_root: String,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    project_roots: &'a [ProjectRoot],
     scan_time: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    part: Option<PartInfo>,
     stats: JsonStats,
     directory_tree: String,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    omitted_files: &'a [OmittedFile],
 }
 
 #[derive(Serialize)]
 struct JsonStats {
     total_files: usize,
     total_tokens: usize,
+    tokenizer: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_budget: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fidelity: Vec<TierStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicates: Option<DuplicateStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    similar: Option<SimilarStats>,
 }
 
 #[derive(Default)]
@@ -67,10 +84,10 @@ impl JsonWriter {
         Self
     }
 
-    fn generate_tree(&self, files: &[FileContext], root_name: &str) -> String {
+    fn generate_tree(&self, files: &[FileContext], meta: &ReportMeta, root_name: &str) -> String {
         let mut root = TreeNode::default();
-        for file in files {
-            root.insert(&file.relative_path);
+        for path in meta.tree_paths(files) {
+            root.insert(path);
         }
         let mut output = String::new();
         output.push_str(&format!("{}\n", root_name));
@@ -83,23 +100,20 @@ impl ContextWriter for JsonWriter {
     fn write<W: Write>(
         &self,
         files: &[FileContext],
+        meta: &ReportMeta,
         config: &ContextConfig,
         writer: W,
     ) -> Result<()> {
         let total_tokens: usize = files.iter().map(|f| f.token_count).sum();
-        let root_name = config
-            .root_path
-            .file_name()
-            .map(|n| n.to_string_lossy())
-            .unwrap_or_else(|| ".".into());
+        let root_name = config.root_name();
 
-        let processed_files: Vec<FileContext> = if config.minify {
+        let processed_files: Vec<FileContext> = if config.transforms_text() {
             files
                 .iter()
                 .map(|f| {
                     let mut new_f = f.clone();
                     if let ContentType::Text(ref t) = f.content {
-                        new_f.content = ContentType::Text(minify_content(t, &f.language));
+                        new_f.content = ContentType::Text(render_text(t, &f.language, config));
                     }
                     new_f
                 })
@@ -111,12 +125,20 @@ impl ContextWriter for JsonWriter {
         let report = JsonReport {
             metadata: JsonMetadata {
                 project_root: config.root_path.to_string_lossy().to_string(),
+                project_roots: &config.roots,
                 scan_time: Local::now().to_rfc3339(),
+                part: meta.part,
                 stats: JsonStats {
                     total_files: files.len(),
                     total_tokens,
+                    tokenizer: config.tokenizer.as_str().to_string(),
+                    token_budget: config.max_tokens,
+                    fidelity: tier_stats(files),
+                    duplicates: Some(meta.duplicates).filter(|d| d.files > 0),
+                    similar: Some(meta.similar).filter(|s| s.files > 0),
                 },
-                directory_tree: self.generate_tree(files, &root_name),
+                directory_tree: self.generate_tree(files, meta, &root_name),
+                omitted_files: &meta.omitted,
             },
             files: &processed_files,
         };
@@ -147,7 +169,7 @@ mod tests {
         let mut buffer = Vec::new();
 
         writer
-            .write(&files, &config, &mut buffer)
+            .write(&files, &ReportMeta::default(), &config, &mut buffer)
             .expect("Should write JSON");
         let output = String::from_utf8(buffer).expect("Valid UTF-8");
 
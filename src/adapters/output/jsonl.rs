@@ -0,0 +1,264 @@
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::core::chunk::chunk_text;
+use crate::core::config::ContextConfig;
+use crate::core::content::{render_text, ContentType, FileContext};
+use crate::core::report::ReportMeta;
+use crate::ports::tokenizer::TokenCounter;
+use crate::ports::writer::ContextWriter;
+
+/// One line of the JSONL export.
+#[derive(Serialize)]
+struct JsonlChunk<'a> {
+    id: String,
+    path: String,
+    language: &'a str,
+    /// Line range the file was cut to (`path:10-20`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lines: Option<String>,
+    /// Symbol the file was cut to (`path#name`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<&'a str>,
+    start_line: usize,
+    end_line: usize,
+    start_byte: usize,
+    end_byte: usize,
+    token_count: usize,
+    text: &'a str,
+}
+
+/// A stretch of a file's text and where it starts in the file.
+struct Region<'a> {
+    text: &'a str,
+    first_line: usize,
+    first_byte: usize,
+}
+
+impl<'a> Region<'a> {
+    /// The body of `file`, or its `--grep-context` excerpts; a line range or
+    /// symbol starts where it was cut from.
+    fn of(file: &'a FileContext) -> Vec<Self> {
+        if !file.excerpts.is_empty() {
+            return file
+                .excerpts
+                .iter()
+                .map(|excerpt| Region {
+                    text: &excerpt.text,
+                    first_line: excerpt.start,
+                    first_byte: excerpt.start_byte,
+                })
+                .collect();
+        }
+        match &file.content {
+            ContentType::Text(text) => vec![Region {
+                text,
+                first_line: file.lines.map_or(1, |lines| lines.start),
+                first_byte: file.start_byte,
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Implementation of ContextWriter that emits one JSON object per chunk, for RAG ingestion.
+pub struct JsonlChunkWriter {
+    token_counter: Arc<dyn TokenCounter>,
+}
+
+impl JsonlChunkWriter {
+    pub fn new(token_counter: Arc<dyn TokenCounter>) -> Self {
+        Self { token_counter }
+    }
+
+    /// Stable id derived from the file path and the chunk's byte range.
+    fn chunk_id(path: &str, start_byte: usize, end_byte: usize) -> String {
+        let digest = Sha256::digest(format!("{}:{}-{}", path, start_byte, end_byte));
+        digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+impl ContextWriter for JsonlChunkWriter {
+    fn write<W: Write>(
+        &self,
+        files: &[FileContext],
+        _meta: &ReportMeta,
+        config: &ContextConfig,
+        mut writer: W,
+    ) -> Result<()> {
+        for file in files {
+            let path = file.relative_path.to_string_lossy();
+            for region in Region::of(file) {
+                // Chunk the text as read so lines and bytes point into the file,
+                // then apply `--strip-comments` / `--minify` to each chunk.
+                let chunks = chunk_text(
+                    region.text,
+                    config.chunk_tokens,
+                    config.chunk_overlap,
+                    self.token_counter.as_ref(),
+                );
+                for chunk in &chunks {
+                    let rendered = render_text(&chunk.text, &file.language, config);
+                    if rendered.trim().is_empty() {
+                        continue;
+                    }
+                    let start_byte = region.first_byte + chunk.start_byte;
+                    let end_byte = region.first_byte + chunk.end_byte;
+                    let record = JsonlChunk {
+                        id: Self::chunk_id(&path, start_byte, end_byte),
+                        path: path.to_string(),
+                        language: &file.language,
+                        lines: file.lines.map(|lines| lines.to_string()),
+                        symbol: file.symbol.as_deref(),
+                        start_line: region.first_line - 1 + chunk.start_line,
+                        end_line: region.first_line - 1 + chunk.end_line,
+                        start_byte,
+                        end_byte,
+                        token_count: self.token_counter.count(&rendered),
+                        text: &rendered,
+                    };
+                    serde_json::to_writer(&mut writer, &record)?;
+                    writeln!(writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::tokenizer::BpeTokenCounter;
+    use crate::core::file::LineRange;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_jsonl_ranges_point_into_the_file() {
+        let config = ContextConfig {
+            chunk_tokens: 8,
+            chunk_overlap: 0,
+            strip_comments: true,
+            ..Default::default()
+        };
+        let text = "// one\n// two\nfn a() {}\n\n// three\nfn b() {}\n";
+        let files = vec![FileContext::new(
+            PathBuf::from("src/lib.rs"),
+            PathBuf::from("src/lib.rs"),
+            ContentType::Text(text.into()),
+            "rs".into(),
+            20,
+        )];
+
+        let writer = JsonlChunkWriter::new(Arc::new(BpeTokenCounter::default()));
+        let mut buffer = Vec::new();
+        writer
+            .write(&files, &ReportMeta::default(), &config, &mut buffer)
+            .expect("Should write JSONL");
+        let output = String::from_utf8(buffer).expect("Valid UTF-8");
+
+        let last: serde_json::Value = serde_json::from_str(output.lines().last().unwrap()).unwrap();
+        assert_eq!(last["text"], "fn b() {}\n");
+        let start = last["start_byte"].as_u64().unwrap() as usize;
+        let end = last["end_byte"].as_u64().unwrap() as usize;
+        assert!(text[start..end].ends_with("fn b() {}\n"));
+        assert_eq!(last["end_line"], 6);
+    }
+
+    #[test]
+    fn test_jsonl_fragment_offsets() {
+        let config = ContextConfig {
+            chunk_tokens: 100,
+            ..Default::default()
+        };
+        let text = "fn a() {}\nfn b() {}\nfn c() {}\n";
+        let fragment = |start: usize| {
+            let range = LineRange { start, end: start };
+            let mut file = FileContext::new(
+                PathBuf::from("x.rs"),
+                PathBuf::from("x.rs"),
+                ContentType::Text(range.slice(text).into()),
+                "rs".into(),
+                5,
+            );
+            file.lines = Some(range);
+            file.start_byte = range.byte_range(text).start;
+            file
+        };
+
+        let writer = JsonlChunkWriter::new(Arc::new(BpeTokenCounter::default()));
+        let mut buffer = Vec::new();
+        writer
+            .write(
+                &[fragment(2), fragment(3)],
+                &ReportMeta::default(),
+                &config,
+                &mut buffer,
+            )
+            .expect("Should write JSONL");
+        let output = String::from_utf8(buffer).expect("Valid UTF-8");
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(records[0]["lines"], "2-2");
+        assert_eq!(records[0]["start_line"], 2);
+        assert_eq!(records[0]["end_line"], 2);
+        let start = records[0]["start_byte"].as_u64().unwrap() as usize;
+        let end = records[0]["end_byte"].as_u64().unwrap() as usize;
+        assert_eq!(&text[start..end], "fn b() {}\n");
+        assert_ne!(records[0]["id"], records[1]["id"]);
+    }
+
+    #[test]
+    fn test_jsonl_chunks_output() {
+        let config = ContextConfig {
+            chunk_tokens: 8,
+            chunk_overlap: 0,
+            ..Default::default()
+        };
+        let files = vec![
+            FileContext::new(
+                PathBuf::from("src/lib.rs"),
+                PathBuf::from("src/lib.rs"),
+                ContentType::Text("fn a() {}\n\nfn b() {}\n\nfn c() {}\n".into()),
+                "rs".into(),
+                15,
+            ),
+            FileContext::new(
+                PathBuf::from("logo.png"),
+                PathBuf::from("logo.png"),
+                ContentType::Binary,
+                "png".into(),
+                0,
+            ),
+        ];
+
+        let writer = JsonlChunkWriter::new(Arc::new(BpeTokenCounter::default()));
+        let mut buffer = Vec::new();
+        writer
+            .write(&files, &ReportMeta::default(), &config, &mut buffer)
+            .expect("Should write JSONL");
+        let output = String::from_utf8(buffer).expect("Valid UTF-8");
+
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("Each line is a JSON object"))
+            .collect();
+        assert!(records.len() > 1);
+        assert!(records.iter().all(|r| r["path"] == "src/lib.rs"));
+        assert_eq!(records[0]["start_line"], 1);
+        assert_eq!(
+            records[0]["id"],
+            JsonlChunkWriter::chunk_id(
+                "src/lib.rs",
+                0,
+                records[0]["end_byte"].as_u64().unwrap() as usize
+            )
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use anyhow::Result;
-use chrono::Local;
+use chrono::{Local, SecondsFormat};
 use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::Path;
 
 use crate::core::config::ContextConfig;
-use crate::core::content::{minify_content, ContentType, FileContext};
+use crate::core::content::{render_text, ContentMode, ContentType, FileContext};
+use crate::core::fidelity::tier_stats;
+use crate::core::report::ReportMeta;
 use crate::ports::writer::ContextWriter;
 
 #[derive(Default)]
@@ -49,10 +51,10 @@ impl MarkdownWriter {
         Self
     }
 
-    fn generate_tree(&self, files: &[FileContext], root_name: &str) -> String {
+    fn generate_tree(&self, files: &[FileContext], meta: &ReportMeta, root_name: &str) -> String {
         let mut root = TreeNode::default();
-        for file in files {
-            root.insert(&file.relative_path);
+        for path in meta.tree_paths(files) {
+            root.insert(path);
         }
 
         let mut output = String::new();
@@ -66,6 +68,7 @@ impl ContextWriter for MarkdownWriter {
     fn write<W: Write>(
         &self,
         files: &[FileContext],
+        meta: &ReportMeta,
         config: &ContextConfig,
         mut writer: W,
     ) -> Result<()> {
@@ -77,39 +80,156 @@ impl ContextWriter for MarkdownWriter {
         )?;
 
         writeln!(writer, "## Metadata")?;
-        writeln!(writer, "- **Root:** `{}`", config.root_path.display())?;
+        if config.roots.is_empty() {
+            writeln!(writer, "- **Root:** `{}`", config.root_path.display())?;
+        } else {
+            writeln!(writer, "- **Roots:**")?;
+            for root in &config.roots {
+                writeln!(writer, "  - `{}`: `{}`", root.label, root.path.display())?;
+            }
+        }
+        if let Some(part) = meta.part {
+            writeln!(writer, "- **Part:** {} of {}", part.index, part.total)?;
+        }
         writeln!(writer, "- **Files:** {}", files.len())?;
 
         let total_tokens: usize = files.iter().map(|f| f.token_count).sum();
-        writeln!(writer, "- **Tokens (Est.):** {}\n", total_tokens)?;
+        writeln!(
+            writer,
+            "- **Tokens ({}):** {}",
+            config.tokenizer.as_str(),
+            total_tokens
+        )?;
+        if let Some(budget) = config.max_tokens {
+            writeln!(writer, "- **Token Budget:** {}", budget)?;
+        }
+        if meta.duplicates.files > 0 {
+            writeln!(
+                writer,
+                "- **Duplicates:** {} files ({} tokens saved)",
+                meta.duplicates.files, meta.duplicates.tokens_saved
+            )?;
+        }
+        if meta.similar.files > 0 {
+            writeln!(
+                writer,
+                "- **Near-duplicates:** {} files in {} clusters ({} tokens saved)",
+                meta.similar.files, meta.similar.clusters, meta.similar.tokens_saved
+            )?;
+        }
+        for tier in tier_stats(files) {
+            writeln!(
+                writer,
+                "- **Fidelity {}:** {} files, {} tokens",
+                tier.fidelity.as_str(),
+                tier.files,
+                tier.tokens
+            )?;
+        }
+        writeln!(writer)?;
 
         writeln!(writer, "## Project Structure")?;
-        let root_name = config
-            .root_path
-            .file_name()
-            .map(|n| n.to_string_lossy())
-            .unwrap_or_else(|| ".".into());
+        let root_name = config.root_name();
 
         writeln!(writer, "```text")?;
         writeln!(
             writer,
             "{}",
-            self.generate_tree(files, &root_name).trim_end()
+            self.generate_tree(files, meta, &root_name).trim_end()
         )?;
         writeln!(writer, "```\n")?;
 
+        if !meta.omitted.is_empty() {
+            writeln!(writer, "## Omitted Files")?;
+            for omitted in &meta.omitted {
+                writeln!(
+                    writer,
+                    "- `{}` ({} tokens, {})",
+                    omitted.relative_path.display(),
+                    omitted.token_count,
+                    omitted.reason.as_str()
+                )?;
+            }
+            writeln!(writer)?;
+        }
+
         writeln!(writer, "## File Contents")?;
 
         for file in files {
-            writeln!(writer, "### `{}`", file.relative_path.display())?;
+            match file.chunk {
+                Some(chunk) => writeln!(
+                    writer,
+                    "### `{}` (chunk {} of {})",
+                    file.relative_path.display(),
+                    chunk.index,
+                    chunk.total
+                )?,
+                None => writeln!(writer, "### `{}`", file.relative_path.display())?,
+            }
+            if !file.mode.is_full() {
+                writeln!(writer, "> *[Mode: {}]*\n", file.mode.as_str())?;
+            }
+            if let Some(original) = &file.duplicate_of {
+                writeln!(writer, "> *[Duplicate of `{}`]*\n", original.display())?;
+                continue;
+            }
+            if let Some(similar) = &file.similar_to {
+                writeln!(
+                    writer,
+                    "> *[Similar to `{}` ({:.0}%): shown as a diff]*\n",
+                    similar.path.display(),
+                    similar.similarity * 100.0
+                )?;
+            }
+            if let Some(target) = &file.link_target {
+                writeln!(writer, "> *[Symlink to `{}`]*\n", target.display())?;
+            }
+            if let Some(encoding) = &file.encoding {
+                writeln!(
+                    writer,
+                    "> *[Encoding: {}, transcoded to UTF-8]*\n",
+                    encoding
+                )?;
+            }
+            if let Some(modified) = file.modified {
+                writeln!(
+                    writer,
+                    "> *[Modified: {}]*\n",
+                    modified.to_rfc3339_opts(SecondsFormat::Secs, true)
+                )?;
+            }
+            if let Some(fidelity) = file.fidelity {
+                writeln!(writer, "> *[Fidelity: {}]*\n", fidelity.as_str())?;
+            }
+            match (&file.symbol, file.lines) {
+                (Some(symbol), Some(lines)) => {
+                    writeln!(writer, "> *[Symbol `{}`, lines {}]*\n", symbol, lines)?
+                }
+                (_, Some(lines)) => writeln!(writer, "> *[Lines {}]*\n", lines)?,
+                _ => {}
+            }
+            if file.truncated {
+                writeln!(writer, "> *[Truncated: see the marker in the content]*\n")?;
+            }
+
+            if let Some(diff) = &file.diff {
+                writeln!(writer, "```diff")?;
+                writeln!(writer, "{}", diff.trim_end())?;
+                writeln!(writer, "```\n")?;
+            }
+
+            for excerpt in &file.excerpts {
+                writeln!(writer, "Lines {}-{}:", excerpt.start, excerpt.end)?;
+                writeln!(writer, "```{}", file.language)?;
+                writeln!(writer, "{}", excerpt.text.trim_end_matches('\n'))?;
+                writeln!(writer, "```\n")?;
+            }
 
             match &file.content {
+                _ if file.is_listed()
+                    || matches!(file.mode, ContentMode::Diff | ContentMode::Excerpts) => {}
                 ContentType::Text(text) => {
-                    let processed = if config.minify {
-                        minify_content(text, &file.language)
-                    } else {
-                        text.to_string()
-                    };
+                    let processed = render_text(text, &file.language, config);
 
                     writeln!(writer, "```{}", file.language)?;
                     writeln!(writer, "{}", processed)?;
@@ -118,6 +238,9 @@ impl ContextWriter for MarkdownWriter {
                 ContentType::Binary => {
                     writeln!(writer, "> *[Binary Content Skipped]*\n")?;
                 }
+                ContentType::Skipped(reason) => {
+                    writeln!(writer, "> *[Skipped: {}]*\n", reason)?;
+                }
                 ContentType::Error(e) => {
                     writeln!(writer, "> *[Error reading file: {}]*\n", e)?;
                 }
@@ -148,7 +271,7 @@ mod tests {
         let mut buffer = Vec::new();
 
         writer
-            .write(&files, &config, &mut buffer)
+            .write(&files, &ReportMeta::default(), &config, &mut buffer)
             .expect("Should write Markdown");
         let output = String::from_utf8(buffer).expect("Valid UTF-8");
 
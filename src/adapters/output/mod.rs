@@ -1,6 +1,7 @@
 //! Output adapters module.
 
 pub mod json;
+pub mod jsonl;
 pub mod markdown;
 pub mod text;
 pub mod xml;
@@ -1,11 +1,13 @@
 use anyhow::Result;
-use chrono::Local;
+use chrono::{Local, SecondsFormat};
 use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::Path;
 
 use crate::core::config::ContextConfig;
-use crate::core::content::{minify_content, ContentType, FileContext};
+use crate::core::content::{render_text, ContentMode, ContentType, FileContext};
+use crate::core::fidelity::tier_stats;
+use crate::core::report::ReportMeta;
 use crate::ports::writer::ContextWriter;
 
 #[derive(Default)]
@@ -49,10 +51,10 @@ impl TextWriter {
         Self
     }
 
-    fn generate_tree(&self, files: &[FileContext], root_name: &str) -> String {
+    fn generate_tree(&self, files: &[FileContext], meta: &ReportMeta, root_name: &str) -> String {
         let mut root = TreeNode::default();
-        for file in files {
-            root.insert(&file.relative_path);
+        for path in meta.tree_paths(files) {
+            root.insert(path);
         }
         let mut output = String::new();
         output.push_str(&format!("{}\n", root_name));
@@ -65,6 +67,7 @@ impl ContextWriter for TextWriter {
     fn write<W: Write>(
         &self,
         files: &[FileContext],
+        meta: &ReportMeta,
         config: &ContextConfig,
         mut writer: W,
     ) -> Result<()> {
@@ -79,49 +82,166 @@ impl ContextWriter for TextWriter {
             "Generated Date: {}",
             Local::now().format("%Y-%m-%d %H:%M:%S")
         )?;
-        writeln!(writer, "Project Root:   {}", config.root_path.display())?;
+        if config.roots.is_empty() {
+            writeln!(writer, "Project Root:   {}", config.root_path.display())?;
+        }
+        for root in &config.roots {
+            writeln!(
+                writer,
+                "Project Root:   {} = {}",
+                root.label,
+                root.path.display()
+            )?;
+        }
+        if let Some(part) = meta.part {
+            writeln!(writer, "Part:           {} of {}", part.index, part.total)?;
+        }
 
         let total_tokens: usize = files.iter().map(|f| f.token_count).sum();
         writeln!(writer, "Total Files:    {}", files.len())?;
-        writeln!(writer, "Total Tokens:   {} (Estimated)", total_tokens)?;
+        writeln!(
+            writer,
+            "Total Tokens:   {} ({})",
+            total_tokens,
+            config.tokenizer.as_str()
+        )?;
+        if let Some(budget) = config.max_tokens {
+            writeln!(writer, "Token Budget:   {}", budget)?;
+        }
+        if meta.duplicates.files > 0 {
+            writeln!(
+                writer,
+                "Duplicates:     {} files ({} tokens saved)",
+                meta.duplicates.files, meta.duplicates.tokens_saved
+            )?;
+        }
+        if meta.similar.files > 0 {
+            writeln!(
+                writer,
+                "Near-dups:      {} files in {} clusters ({} tokens saved)",
+                meta.similar.files, meta.similar.clusters, meta.similar.tokens_saved
+            )?;
+        }
+        for tier in tier_stats(files) {
+            writeln!(
+                writer,
+                "{:<16}{} files, {} tokens",
+                format!("Tier {}:", tier.fidelity.as_str()),
+                tier.files,
+                tier.tokens
+            )?;
+        }
         writeln!(writer, "\n")?;
 
         writeln!(writer, "DIRECTORY STRUCTURE")?;
         writeln!(writer, "{}", sub_separator)?;
-        let root_name = config
-            .root_path
-            .file_name()
-            .map(|n| n.to_string_lossy())
-            .unwrap_or_else(|| ".".into());
+        let root_name = config.root_name();
 
         writeln!(
             writer,
             "{}",
-            self.generate_tree(files, &root_name).trim_end()
+            self.generate_tree(files, meta, &root_name).trim_end()
         )?;
         writeln!(writer, "\n\n")?;
 
+        if !meta.omitted.is_empty() {
+            writeln!(writer, "OMITTED FILES")?;
+            writeln!(writer, "{}", sub_separator)?;
+            for omitted in &meta.omitted {
+                writeln!(
+                    writer,
+                    "{} ({} tokens, {})",
+                    omitted.relative_path.display(),
+                    omitted.token_count,
+                    omitted.reason.as_str()
+                )?;
+            }
+            writeln!(writer, "\n")?;
+        }
+
         writeln!(writer, "FILE CONTENTS")?;
         writeln!(writer, "{}", separator)?;
 
         for file in files {
             writeln!(writer, "\nFILE: {}", file.relative_path.display())?;
             writeln!(writer, "LANGUAGE: {}", file.language)?;
+            if !file.mode.is_full() {
+                writeln!(writer, "MODE: {}", file.mode.as_str())?;
+            }
+            if let Some(original) = &file.duplicate_of {
+                writeln!(writer, "DUPLICATE OF: {}", original.display())?;
+            }
+            if let Some(similar) = &file.similar_to {
+                writeln!(
+                    writer,
+                    "SIMILAR TO: {} ({:.2}), shown as a diff",
+                    similar.path.display(),
+                    similar.similarity
+                )?;
+            }
+            if let Some(target) = &file.link_target {
+                writeln!(writer, "LINK TARGET: {}", target.display())?;
+            }
+            if let Some(encoding) = &file.encoding {
+                writeln!(writer, "ENCODING: {}", encoding)?;
+            }
+            if let Some(modified) = file.modified {
+                writeln!(
+                    writer,
+                    "MODIFIED: {}",
+                    modified.to_rfc3339_opts(SecondsFormat::Secs, true)
+                )?;
+            }
+            if let Some(fidelity) = file.fidelity {
+                writeln!(writer, "FIDELITY: {}", fidelity.as_str())?;
+            }
+            if let Some(symbol) = &file.symbol {
+                writeln!(writer, "SYMBOL: {}", symbol)?;
+            }
+            if let Some(lines) = file.lines {
+                writeln!(writer, "LINES: {}", lines)?;
+            }
+            if file.truncated {
+                writeln!(writer, "TRUNCATED: see the marker in the content")?;
+            }
+            if let Some(chunk) = file.chunk {
+                writeln!(writer, "CHUNK: {} of {}", chunk.index, chunk.total)?;
+            }
             writeln!(writer, "{}", sub_separator)?;
 
+            if let Some(diff) = &file.diff {
+                writeln!(writer, "{}", diff.trim_end())?;
+                writeln!(writer, "{}", sub_separator)?;
+                if file.mode == ContentMode::Diff {
+                    continue;
+                }
+            }
+
+            if file.mode == ContentMode::Excerpts {
+                for excerpt in &file.excerpts {
+                    writeln!(writer, "LINES {}-{}:", excerpt.start, excerpt.end)?;
+                    writeln!(writer, "{}", excerpt.text.trim_end_matches('\n'))?;
+                    writeln!(writer, "{}", sub_separator)?;
+                }
+                continue;
+            }
+
             match &file.content {
+                _ if file.is_listed() => writeln!(writer, "[LISTED ONLY]")?,
+                _ if file.duplicate_of.is_some() => {
+                    writeln!(writer, "[DUPLICATE CONTENT SKIPPED]")?
+                }
                 ContentType::Text(text) => {
                     // APPLY MINIFICATION (UPDATED)
-                    let processed = if config.minify {
-                        minify_content(text, &file.language)
-                    } else {
-                        text.to_string()
-                    };
+                    let processed = render_text(text, &file.language, config);
                     writeln!(writer, "{}", processed)?;
                 }
                 ContentType::Binary => {
                     writeln!(writer, "[BINARY CONTENT SKIPPED]")?;
                 }
+                ContentType::Skipped(reason) => {
+                    writeln!(writer, "[SKIPPED: {}]", reason)?;
+                }
                 ContentType::Error(e) => {
                     writeln!(writer, "[ERROR READING FILE: {}]", e)?;
                 }
@@ -154,7 +274,7 @@ mod tests {
         let mut buffer = Vec::new();
 
         writer
-            .write(&files, &config, &mut buffer)
+            .write(&files, &ReportMeta::default(), &config, &mut buffer)
             .expect("Should write Text");
         let output = String::from_utf8(buffer).expect("Valid UTF-8");
 
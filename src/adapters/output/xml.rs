@@ -1,5 +1,5 @@
 use anyhow::Result;
-use chrono::Local;
+use chrono::{Local, SecondsFormat};
 use quick_xml::events::{BytesCData, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::Writer;
 use std::collections::BTreeMap;
@@ -7,7 +7,9 @@ use std::io::Write;
 use std::path::Path;
 
 use crate::core::config::ContextConfig;
-use crate::core::content::{minify_content, ContentType, FileContext};
+use crate::core::content::{render_text, ContentMode, ContentType, FileContext};
+use crate::core::fidelity::tier_stats;
+use crate::core::report::ReportMeta;
 use crate::ports::writer::ContextWriter;
 
 /// Internal struct to represent the directory tree in memory before printing.
@@ -23,10 +25,7 @@ impl TreeNode {
         let mut current_node = self;
         for component in path.components() {
             let key = component.as_os_str().to_string_lossy().to_string();
-            current_node = current_node
-                .children
-                .entry(key)
-                .or_insert_with(TreeNode::default);
+            current_node = current_node.children.entry(key).or_default();
         }
         current_node.is_file = true;
     }
@@ -63,11 +62,11 @@ impl XmlWriter {
     }
 
     /// Generates a recursive ASCII tree representation.
-    fn generate_tree(&self, files: &[FileContext], root_name: &str) -> String {
+    fn generate_tree(&self, files: &[FileContext], meta: &ReportMeta, root_name: &str) -> String {
         let mut root_node = TreeNode::default();
 
-        for file in files {
-            root_node.insert(&file.relative_path);
+        for path in meta.tree_paths(files) {
+            root_node.insert(path);
         }
 
         let mut output = String::new();
@@ -90,6 +89,7 @@ impl ContextWriter for XmlWriter {
     fn write<W: Write>(
         &self,
         files: &[FileContext],
+        meta: &ReportMeta,
         config: &ContextConfig,
         writer: W,
     ) -> Result<()> {
@@ -99,12 +99,29 @@ impl ContextWriter for XmlWriter {
 
         // 1. Metadata
         xml_writer.write_event(Event::Start(BytesStart::new("metadata")))?;
-        xml_writer
-            .create_element("project_root")
-            .write_text_content(BytesText::new(&config.root_path.to_string_lossy()))?;
+        if config.roots.is_empty() {
+            xml_writer
+                .create_element("project_root")
+                .write_text_content(BytesText::new(&config.root_path.to_string_lossy()))?;
+        } else {
+            xml_writer.write_event(Event::Start(BytesStart::new("project_roots")))?;
+            for root in &config.roots {
+                let mut elem = BytesStart::new("root");
+                elem.push_attribute(("label", root.label.as_str()));
+                elem.push_attribute(("path", root.path.to_string_lossy().as_ref()));
+                xml_writer.write_event(Event::Empty(elem))?;
+            }
+            xml_writer.write_event(Event::End(BytesEnd::new("project_roots")))?;
+        }
         xml_writer
             .create_element("scan_time")
             .write_text_content(BytesText::new(&Local::now().to_rfc3339()))?;
+        if let Some(part) = meta.part {
+            let mut elem = BytesStart::new("part");
+            elem.push_attribute(("index", part.index.to_string().as_str()));
+            elem.push_attribute(("total", part.total.to_string().as_str()));
+            xml_writer.write_event(Event::Empty(elem))?;
+        }
 
         xml_writer.write_event(Event::Start(BytesStart::new("stats")))?;
         xml_writer
@@ -114,18 +131,65 @@ impl ContextWriter for XmlWriter {
         xml_writer
             .create_element("total_tokens")
             .write_text_content(BytesText::new(&total_tokens.to_string()))?;
+        xml_writer
+            .create_element("tokenizer")
+            .write_text_content(BytesText::new(config.tokenizer.as_str()))?;
+        if let Some(budget) = config.max_tokens {
+            xml_writer
+                .create_element("token_budget")
+                .write_text_content(BytesText::new(&budget.to_string()))?;
+        }
+        if meta.duplicates.files > 0 {
+            let mut elem = BytesStart::new("duplicates");
+            elem.push_attribute(("files", meta.duplicates.files.to_string().as_str()));
+            elem.push_attribute((
+                "tokens_saved",
+                meta.duplicates.tokens_saved.to_string().as_str(),
+            ));
+            xml_writer.write_event(Event::Empty(elem))?;
+        }
+        if meta.similar.files > 0 {
+            let mut elem = BytesStart::new("similar");
+            elem.push_attribute(("clusters", meta.similar.clusters.to_string().as_str()));
+            elem.push_attribute(("files", meta.similar.files.to_string().as_str()));
+            elem.push_attribute((
+                "tokens_saved",
+                meta.similar.tokens_saved.to_string().as_str(),
+            ));
+            xml_writer.write_event(Event::Empty(elem))?;
+        }
+        let tiers = tier_stats(files);
+        if !tiers.is_empty() {
+            xml_writer.write_event(Event::Start(BytesStart::new("fidelity")))?;
+            for tier in tiers {
+                let mut elem = BytesStart::new("tier");
+                elem.push_attribute(("name", tier.fidelity.as_str()));
+                elem.push_attribute(("files", tier.files.to_string().as_str()));
+                elem.push_attribute(("tokens", tier.tokens.to_string().as_str()));
+                xml_writer.write_event(Event::Empty(elem))?;
+            }
+            xml_writer.write_event(Event::End(BytesEnd::new("fidelity")))?;
+        }
         xml_writer.write_event(Event::End(BytesEnd::new("stats")))?;
 
-        let root_name = config
-            .root_path
-            .file_name()
-            .map(|n| n.to_string_lossy())
-            .unwrap_or_else(|| ".".into());
-        let tree_view = self.generate_tree(files, &root_name);
+        let root_name = config.root_name();
+        let tree_view = self.generate_tree(files, meta, &root_name);
         xml_writer
             .create_element("directory_structure")
             .write_text_content(BytesText::new(&tree_view))?;
 
+        if !meta.omitted.is_empty() {
+            xml_writer.write_event(Event::Start(BytesStart::new("omitted_files")))?;
+            for omitted in &meta.omitted {
+                let mut elem = BytesStart::new("file");
+                elem.push_attribute(("path", omitted.relative_path.to_string_lossy().as_ref()));
+                elem.push_attribute(("tokens", omitted.token_count.to_string().as_str()));
+                elem.push_attribute(("reason", omitted.reason.as_str()));
+                xml_writer.write_event(Event::Empty(elem))?;
+            }
+            xml_writer.write_event(Event::End(BytesEnd::new("omitted_files")))?;
+        }
+
         xml_writer.write_event(Event::End(BytesEnd::new("metadata")))?;
 
         // 2. Files
@@ -134,17 +198,73 @@ impl ContextWriter for XmlWriter {
         for file in files {
             let mut elem = BytesStart::new("file");
             elem.push_attribute(("path", file.relative_path.to_string_lossy().as_ref()));
+            if let Some(original) = &file.duplicate_of {
+                elem.push_attribute(("duplicate_of", original.to_string_lossy().as_ref()));
+                xml_writer.write_event(Event::Empty(elem))?;
+                continue;
+            }
+            if let Some(root) = &file.root {
+                elem.push_attribute(("root", root.as_str()));
+            }
             elem.push_attribute(("language", file.language.as_str()));
+            if !file.mode.is_full() {
+                elem.push_attribute(("mode", file.mode.as_str()));
+            }
+            if let Some(similar) = &file.similar_to {
+                elem.push_attribute(("similar_to", similar.path.to_string_lossy().as_ref()));
+                elem.push_attribute(("similarity", format!("{:.2}", similar.similarity).as_str()));
+            }
+            if let Some(target) = &file.link_target {
+                elem.push_attribute(("link_target", target.to_string_lossy().as_ref()));
+            }
+            if let Some(encoding) = &file.encoding {
+                elem.push_attribute(("encoding", encoding.as_str()));
+            }
+            if let Some(modified) = file.modified {
+                let modified = modified.to_rfc3339_opts(SecondsFormat::Secs, true);
+                elem.push_attribute(("modified", modified.as_str()));
+            }
+            if let Some(fidelity) = file.fidelity {
+                elem.push_attribute(("fidelity", fidelity.as_str()));
+            }
+            if let Some(symbol) = &file.symbol {
+                elem.push_attribute(("symbol", symbol.as_str()));
+            }
+            if let Some(lines) = file.lines {
+                elem.push_attribute(("lines", lines.to_string().as_str()));
+            }
+            if file.truncated {
+                elem.push_attribute(("truncated", "true"));
+            }
+            if let Some(chunk) = file.chunk {
+                elem.push_attribute(("chunk", format!("{}/{}", chunk.index, chunk.total).as_str()));
+            }
 
+            if file.is_listed() {
+                xml_writer.write_event(Event::Empty(elem))?;
+                continue;
+            }
             xml_writer.write_event(Event::Start(elem))?;
 
+            if let Some(diff) = &file.diff {
+                xml_writer
+                    .create_element("diff")
+                    .write_cdata_content(BytesCData::new(self.sanitize_content(diff)))?;
+            }
+
+            // Excerpts are emitted verbatim so their line numbers stay valid.
+            for excerpt in &file.excerpts {
+                xml_writer
+                    .create_element("excerpt")
+                    .with_attribute(("start", excerpt.start.to_string().as_str()))
+                    .with_attribute(("end", excerpt.end.to_string().as_str()))
+                    .write_cdata_content(BytesCData::new(self.sanitize_content(&excerpt.text)))?;
+            }
+
             match &file.content {
+                _ if matches!(file.mode, ContentMode::Diff | ContentMode::Excerpts) => {}
                 ContentType::Text(text) => {
-                    let processed = if config.minify {
-                        minify_content(text, &file.language)
-                    } else {
-                        text.clone()
-                    };
+                    let processed = render_text(text, &file.language, config);
 
                     let sanitized = self.sanitize_content(&processed);
                     xml_writer.write_event(Event::CData(BytesCData::new(&sanitized)))?;
@@ -153,6 +273,12 @@ impl ContextWriter for XmlWriter {
                     xml_writer
                         .write_event(Event::CData(BytesCData::new("[BINARY CONTENT SKIPPED]")))?;
                 }
+                ContentType::Skipped(reason) => {
+                    xml_writer.write_event(Event::CData(BytesCData::new(format!(
+                        "[SKIPPED: {}]",
+                        reason
+                    ))))?;
+                }
                 ContentType::Error(e) => {
                     xml_writer.write_event(Event::CData(BytesCData::new(format!(
                         "[ERROR READING FILE: {}]",
@@ -204,7 +330,7 @@ mod tests {
         ];
 
         let writer = XmlWriter::new();
-        let tree = writer.generate_tree(&files, "my_project");
+        let tree = writer.generate_tree(&files, &ReportMeta::default(), "my_project");
 
         println!("Generated tree:\n{}", tree);
 
@@ -1,10 +1,9 @@
 use crate::adapters::parsers::FileParser;
 use anyhow::{Context, Result};
 use regex::Regex;
-use std::fs::File;
-use std::io::Read;
-use std::path::Path;
+use std::io::{Cursor, Read};
 
+#[derive(Default)]
 pub struct DocxParser;
 
 impl DocxParser {
@@ -14,9 +13,8 @@ impl DocxParser {
 }
 
 impl FileParser for DocxParser {
-    fn parse(&self, path: &Path) -> Result<String> {
-        let file = File::open(path)?;
-        let mut archive = zip::ZipArchive::new(file)?;
+    fn parse_bytes(&self, bytes: &[u8]) -> Result<String> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
 
         let mut document_xml = archive
             .by_name("word/document.xml")
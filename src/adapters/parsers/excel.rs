@@ -1,8 +1,9 @@
 use crate::adapters::parsers::FileParser;
 use anyhow::{Context, Result};
-use calamine::{open_workbook, Data, Reader, Xlsx};
-use std::path::Path;
+use calamine::{Data, Reader, Xlsx};
+use std::io::Cursor;
 
+#[derive(Default)]
 pub struct ExcelParser;
 
 impl ExcelParser {
@@ -12,9 +13,9 @@ impl ExcelParser {
 }
 
 impl FileParser for ExcelParser {
-    fn parse(&self, path: &Path) -> Result<String> {
-        let mut workbook: Xlsx<_> =
-            open_workbook(path).with_context(|| "Cannot open Excel file")?;
+    fn parse_bytes(&self, bytes: &[u8]) -> Result<String> {
+        let mut workbook =
+            Xlsx::new(Cursor::new(bytes)).with_context(|| "Cannot open Excel file")?;
 
         let mut output = String::new();
 
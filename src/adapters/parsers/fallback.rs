@@ -1,19 +1,131 @@
 use crate::adapters::parsers::FileParser;
-use anyhow::Result;
-use std::fs;
+use anyhow::{bail, Result};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use std::fs::{self, File};
+use std::io::Read;
 use std::path::Path;
 
+/// Text decoded to UTF-8 together with the encoding it was stored in.
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: &'static Encoding,
+}
+
+#[derive(Default)]
 pub struct PlainTextParser;
 
 impl PlainTextParser {
     pub fn new() -> Self {
         Self
     }
+
+    /// Reads the whole file and transcodes it to UTF-8.
+    pub fn read(&self, path: &Path) -> Result<DecodedText> {
+        self.read_bytes(&fs::read(path)?)
+    }
+
+    /// Transcodes the whole contents of a file to UTF-8.
+    pub fn read_bytes(&self, bytes: &[u8]) -> Result<DecodedText> {
+        decode(bytes, true)
+    }
+
+    /// Reads at most `max_bytes`, cut back to the last complete line (or
+    /// character when the head holds a single line).
+    pub fn parse_head(&self, path: &Path, max_bytes: u64) -> Result<DecodedText> {
+        let mut head = Vec::new();
+        File::open(path)?.take(max_bytes).read_to_end(&mut head)?;
+        self.decode_head(&head)
+    }
+
+    /// Same as `parse_head`, for the first bytes of a file already in memory.
+    pub fn decode_head(&self, head: &[u8]) -> Result<DecodedText> {
+        let mut decoded = decode(head, false)?;
+        let end = decoded
+            .text
+            .rfind('\n')
+            .map_or(decoded.text.len(), |p| p + 1);
+        decoded.text.truncate(end);
+        Ok(decoded)
+    }
 }
 
 impl FileParser for PlainTextParser {
-    fn parse(&self, path: &Path) -> Result<String> {
-        let text = fs::read_to_string(path)?;
-        Ok(text)
+    fn parse_bytes(&self, bytes: &[u8]) -> Result<String> {
+        Ok(self.read_bytes(bytes)?.text)
+    }
+}
+
+/// Decodes `bytes` using, in order: its BOM, UTF-8, or the charset guessed by
+/// `chardetng`. When `complete` is false the bytes are a prefix of the file and
+/// an incomplete character at the end is dropped instead of rejected.
+fn decode(bytes: &[u8], complete: bool) -> Result<DecodedText> {
+    let encoding = match Encoding::for_bom(bytes) {
+        Some((encoding, _)) => encoding,
+        None if is_utf8(bytes, complete) => UTF_8,
+        None => {
+            let mut detector = EncodingDetector::new();
+            detector.feed(bytes, complete);
+            detector.guess(None, false)
+        }
+    };
+
+    let mut decoder = encoding.new_decoder_with_bom_removal();
+    let capacity = decoder
+        .max_utf8_buffer_length(bytes.len())
+        .unwrap_or(bytes.len());
+    let mut text = String::with_capacity(capacity);
+    let (_, _, had_errors) = decoder.decode_to_string(bytes, &mut text, complete);
+    if had_errors {
+        bail!("not valid {} text", encoding.name());
+    }
+    // Single-byte charsets map every byte, so a guess over binary data always
+    // "succeeds"; control characters give it away.
+    if encoding != UTF_8 && Encoding::for_bom(bytes).is_none() && looks_binary(&text) {
+        bail!("binary data");
+    }
+    Ok(DecodedText { text, encoding })
+}
+
+fn is_utf8(bytes: &[u8], complete: bool) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => !complete && e.error_len().is_none(),
+    }
+}
+
+fn looks_binary(text: &str) -> bool {
+    let controls = text
+        .chars()
+        .filter(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+        .count();
+    controls * 100 > text.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_legacy_encodings() {
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("año\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let decoded = decode(&utf16, true).unwrap();
+        assert_eq!(
+            (decoded.text.as_str(), decoded.encoding.name()),
+            ("año\n", "UTF-16LE")
+        );
+
+        let latin = b"// Configuraci\xf3n del m\xf3dulo de facturaci\xf3n\nlet a\xf1o = 2024;\n";
+        let decoded = decode(latin, true).unwrap();
+        assert_eq!(decoded.encoding.name(), "windows-1252");
+        assert!(decoded.text.contains("Configuración"));
+
+        let bom = decode(b"\xef\xbb\xbfplain", true).unwrap();
+        assert_eq!((bom.text.as_str(), bom.encoding), ("plain", UTF_8));
+
+        assert!(decode(b"\x01\x02\x03\x80\x81", true).is_err());
     }
 }
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use std::fs;
 use std::path::Path;
 
 pub mod docx;
@@ -8,6 +9,11 @@ pub mod pdf;
 
 /// Strategy interface for parsing specific file formats.
 pub trait FileParser: Send + Sync {
+    /// Extracts text content from the raw bytes of a file, e.g. a git blob.
+    fn parse_bytes(&self, bytes: &[u8]) -> Result<String>;
+
     /// Extracts text content from the file at the given path.
-    fn parse(&self, path: &Path) -> Result<String>;
+    fn parse(&self, path: &Path) -> Result<String> {
+        self.parse_bytes(&fs::read(path)?)
+    }
 }
@@ -3,9 +3,9 @@ use anyhow::{Context, Result};
 use lopdf::Document;
 use regex::Regex;
 use std::panic;
-use std::path::Path;
 use tracing::{debug, warn};
 
+#[derive(Default)]
 pub struct PdfParser;
 
 impl PdfParser {
@@ -37,10 +37,8 @@ impl PdfParser {
                 continue;
             }
 
-            if re_pagination.is_match(trimmed) {
-                if trimmed.len() < 40 {
-                    continue;
-                }
+            if re_pagination.is_match(trimmed) && trimmed.len() < 40 {
+                continue;
             }
 
             if trimmed.len() < 3 && !trimmed.starts_with('-') && !trimmed.starts_with('•') {
@@ -83,12 +81,11 @@ impl PdfParser {
 }
 
 impl FileParser for PdfParser {
-    fn parse(&self, path: &Path) -> Result<String> {
-        debug!("Parsing PDF using lopdf: {:?}", path);
-        let path_buf = path.to_path_buf();
+    fn parse_bytes(&self, bytes: &[u8]) -> Result<String> {
+        debug!("Parsing PDF using lopdf ({} bytes)", bytes.len());
 
         let result = panic::catch_unwind(move || {
-            let doc = Document::load(&path_buf).context("Failed to load PDF document")?;
+            let doc = Document::load_mem(bytes).context("Failed to load PDF document")?;
             let pages = doc.get_pages();
             let mut full_text = String::new();
 
@@ -0,0 +1,29 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::core::noise::NoiseSettings;
+
+/// Optional per-project settings file, looked up in the project root.
+pub const PROJECT_CONFIG_FILE: &str = ".context.toml";
+
+/// Contents of `.context.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub noise: NoiseSettings,
+}
+
+impl ProjectConfig {
+    /// Loads the config file from `root`. A missing file yields the defaults.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(PROJECT_CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))
+    }
+}
@@ -0,0 +1,98 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::core::file::FileKind;
+
+/// Bytes inspected at the start of each file.
+pub const SNIFF_LEN: usize = 8192;
+
+/// Extensions routed to a document parser.
+const DOCUMENT_EXTENSIONS: &[&str] = &["pdf", "docx", "xlsx", "xls"];
+
+/// Byte-order marks of Unicode text encodings.
+const BOMS: &[&[u8]] = &[
+    b"\xEF\xBB\xBF",
+    b"\xFF\xFE",
+    b"\xFE\xFF",
+    b"\x00\x00\xFE\xFF",
+];
+
+/// Signatures of common binary formats that may not contain a NUL byte early on.
+const MAGIC_NUMBERS: &[&[u8]] = &[
+    b"\x89PNG\r\n\x1A\n",
+    b"\xFF\xD8\xFF",
+    b"GIF87a",
+    b"GIF89a",
+    b"PK\x03\x04",
+    b"\x1F\x8B",
+    b"\xFD7zXZ\x00",
+    b"7z\xBC\xAF\x27\x1C",
+    b"Rar!\x1A\x07",
+    b"%PDF-",
+    b"\x7FELF",
+    b"\0asm",
+    b"\xCA\xFE\xBA\xBE",
+    b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1",
+    b"SQLite format 3\0",
+    b"wOFF",
+    b"wOF2",
+];
+
+/// Classifies a file from its extension and its first `SNIFF_LEN` bytes.
+pub fn sniff_file(path: &Path) -> io::Result<FileKind> {
+    if is_document(path) {
+        return Ok(FileKind::Document);
+    }
+
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    Ok(sniff_bytes(&head))
+}
+
+/// Same as `sniff_file`, for a file whose contents are already in memory.
+pub fn sniff_contents(path: &Path, contents: &[u8]) -> FileKind {
+    if is_document(path) {
+        return FileKind::Document;
+    }
+    sniff_bytes(&contents[..contents.len().min(SNIFF_LEN)])
+}
+
+fn is_document(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| DOCUMENT_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Text unless the sample starts with a known binary signature or contains a
+/// NUL byte. A Unicode byte-order mark always means text.
+pub fn sniff_bytes(head: &[u8]) -> FileKind {
+    if BOMS.iter().any(|bom| head.starts_with(bom)) {
+        return FileKind::Text;
+    }
+    if MAGIC_NUMBERS.iter().any(|magic| head.starts_with(magic)) || head.contains(&0) {
+        return FileKind::Binary;
+    }
+    FileKind::Text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_bytes() {
+        assert_eq!(sniff_bytes(b"fn main() {}\n"), FileKind::Text);
+        assert_eq!(sniff_bytes(b""), FileKind::Text);
+        assert_eq!(sniff_bytes(b"\x89PNG\r\n\x1A\n...."), FileKind::Binary);
+        assert_eq!(sniff_bytes(b"GIF89a no nul here"), FileKind::Binary);
+        assert_eq!(sniff_bytes(b"abc\0def"), FileKind::Binary);
+        assert_eq!(
+            sniff_bytes(b"\xFF\xFEh\0i\0"),
+            FileKind::Text,
+            "UTF-16 text has NUL bytes but a BOM"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use tiktoken_rs::CoreBPE;
+
+use crate::core::config::TokenizerKind;
+use crate::ports::tokenizer::TokenCounter;
+
+/// Offline BPE token counter backed by the vocab files embedded in the binary.
+pub struct BpeTokenCounter {
+    kind: TokenizerKind,
+    bpe: &'static CoreBPE,
+}
+
+impl Default for BpeTokenCounter {
+    fn default() -> Self {
+        Self::new(TokenizerKind::default())
+    }
+}
+
+impl BpeTokenCounter {
+    /// Creates a counter for the requested encoding. The vocab is loaded once per process.
+    pub fn new(kind: TokenizerKind) -> Self {
+        let bpe = match kind {
+            TokenizerKind::Cl100k => tiktoken_rs::cl100k_base_singleton(),
+            TokenizerKind::O200k => tiktoken_rs::o200k_base_singleton(),
+        };
+        Self { kind, bpe }
+    }
+}
+
+impl TokenCounter for BpeTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        self.bpe.count_ordinary(text)
+    }
+
+    fn name(&self) -> &str {
+        self.kind.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bpe_counts_known_strings() {
+        let cl100k = BpeTokenCounter::new(TokenizerKind::Cl100k);
+        let o200k = BpeTokenCounter::new(TokenizerKind::O200k);
+
+        assert_eq!(cl100k.count(""), 0);
+        assert_eq!(cl100k.count("hello world"), 2);
+        assert_eq!(o200k.count("hello world"), 2);
+        assert_eq!(o200k.name(), "o200k");
+    }
+
+    #[test]
+    fn test_bpe_differs_from_byte_heuristic() {
+        let counter = BpeTokenCounter::default();
+
+        // Deep indentation is merged into very few tokens.
+        let indented = format!("{}x", " ".repeat(120));
+        assert!(counter.count(&indented) < indented.len() / 3);
+
+        // CJK text needs far more tokens than `len / 3` suggests per character.
+        let cjk = "日本語のテキストを数える";
+        assert!(counter.count(cjk) >= cjk.chars().count() / 2);
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::core::config::ContextConfig;
+use crate::core::content::{ContentMode, FileContext};
+use crate::core::pattern::PathMatcher;
+use crate::core::report::{OmissionReason, OmittedFile};
+use crate::core::skeleton::apply_skeleton;
+use crate::ports::tokenizer::TokenCounter;
+
+/// Selects the files that fit in `max_tokens`, returning the kept files (in their
+/// original order) and the ones left out.
+///
+/// `overhead` is what the report costs before any file is kept (wrappers,
+/// metadata, the full tree and every file listed as omitted); `cost` is what a
+/// file adds when kept instead of listed.
+///
+/// Priority policy:
+/// 1. Files matched by an `--include-path` glob.
+/// 2. Shallower paths before deeper ones.
+/// 3. Smaller files before larger ones.
+///
+/// Selection is greedy: a file that does not fit is elided to its skeleton when
+/// that fits, otherwise skipped, and smaller candidates are still tried. A
+/// duplicate or collapsed sibling is only kept together with the file it refers
+/// to, which is pulled in (and paid for) with it when needed; neither is elided.
+pub fn apply_token_budget(
+    mut files: Vec<FileContext>,
+    max_tokens: usize,
+    overhead: usize,
+    cost: &dyn Fn(&FileContext) -> usize,
+    config: &ContextConfig,
+    counter: &dyn TokenCounter,
+) -> (Vec<FileContext>, Vec<OmittedFile>) {
+    // The scanner already rejected invalid globs.
+    let include = PathMatcher::new(&config.include_paths).ok();
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    order.sort_by_key(|&i| {
+        let file = &files[i];
+        (
+            !is_explicit_include(file, include.as_ref()),
+            file.relative_path.components().count(),
+            file.token_count,
+            file.relative_path.clone(),
+        )
+    });
+
+    let index: HashMap<&PathBuf, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| (&file.relative_path, i))
+        .collect();
+    let references: Vec<Option<usize>> = files
+        .iter()
+        .map(|file| {
+            file.duplicate_of
+                .as_ref()
+                .or(file.similar_to.as_ref().map(|s| &s.path))
+                .and_then(|path| index.get(path).copied())
+        })
+        .collect();
+    let referenced: HashSet<usize> = references.iter().flatten().copied().collect();
+
+    let budget = max_tokens.saturating_sub(overhead);
+    let mut keep = vec![false; files.len()];
+    let mut used = 0usize;
+    for i in order {
+        if keep[i] {
+            continue;
+        }
+        // The file plus the not yet kept files it refers to, transitively.
+        let mut needed = vec![i];
+        while let Some(r) = references[needed[needed.len() - 1]] {
+            if keep[r] || needed.contains(&r) {
+                break;
+            }
+            needed.push(r);
+        }
+        let tokens: usize = needed.iter().map(|&j| cost(&files[j])).sum();
+        if used + tokens <= budget {
+            used += tokens;
+            for j in needed {
+                keep[j] = true;
+            }
+            continue;
+        }
+
+        if references[i].is_some() || referenced.contains(&i) {
+            continue;
+        }
+        if let Some(elided) = elide(&files[i], config, counter) {
+            let tokens = cost(&elided);
+            if used + tokens <= budget {
+                used += tokens;
+                files[i] = elided;
+                keep[i] = true;
+            }
+        }
+    }
+
+    let mut kept = Vec::new();
+    let mut omitted = Vec::new();
+    for (i, file) in files.into_iter().enumerate() {
+        if keep[i] {
+            kept.push(file);
+            continue;
+        }
+        let reason = match references[i] {
+            Some(r) if !keep[r] => OmissionReason::ReferenceOmitted,
+            _ => OmissionReason::TokenBudget,
+        };
+        omitted.push(OmittedFile {
+            relative_path: file.relative_path,
+            token_count: file.token_count,
+            reason,
+        });
+    }
+
+    (kept, omitted)
+}
+
+/// The file reduced to its skeleton, when it is still a full body that can be reduced.
+fn elide(
+    file: &FileContext,
+    config: &ContextConfig,
+    counter: &dyn TokenCounter,
+) -> Option<FileContext> {
+    if file.mode != ContentMode::Full || file.diff.is_some() || file.fidelity.is_some() {
+        return None;
+    }
+    let mut elided = file.clone();
+    if !apply_skeleton(&mut elided, counter) {
+        return None;
+    }
+    if config.transforms_text() {
+        elided.count_rendered(config, counter);
+    }
+    (elided.token_count < file.token_count).then_some(elided)
+}
+
+/// A file is explicitly included when an include glob matches its path
+/// relative to its own root.
+fn is_explicit_include(file: &FileContext, include: Option<&PathMatcher>) -> bool {
+    let Some(include) = include.filter(|include| !include.is_empty()) else {
+        return false;
+    };
+    include.is_match(file.root_relative_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::content::{ContentType, SimilarTo};
+
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+
+        fn name(&self) -> &str {
+            "words"
+        }
+    }
+
+    fn budget(
+        files: Vec<FileContext>,
+        max_tokens: usize,
+        config: &ContextConfig,
+    ) -> (Vec<FileContext>, Vec<OmittedFile>) {
+        let cost = |file: &FileContext| file.token_count;
+        apply_token_budget(files, max_tokens, 0, &cost, config, &WordCounter)
+    }
+
+    fn file(path: &str, tokens: usize) -> FileContext {
+        FileContext::new(
+            PathBuf::from(path),
+            PathBuf::from(path),
+            ContentType::Text(String::new()),
+            "txt".into(),
+            tokens,
+        )
+    }
+
+    #[test]
+    fn test_budget_prefers_shallow_small_files() {
+        let files = vec![
+            file("a/b/deep.rs", 10),
+            file("big.rs", 80),
+            file("small.rs", 20),
+            file("a/mid.rs", 30),
+        ];
+
+        let (kept, omitted) = budget(files, 60, &ContextConfig::default());
+        let kept: Vec<_> = kept.iter().map(|f| f.relative_path.clone()).collect();
+
+        // small.rs (20) + a/mid.rs (30) + a/b/deep.rs (10) fit; big.rs does not.
+        assert_eq!(
+            kept,
+            vec![
+                PathBuf::from("a/b/deep.rs"),
+                PathBuf::from("small.rs"),
+                PathBuf::from("a/mid.rs")
+            ]
+        );
+        assert_eq!(omitted.len(), 1);
+        assert_eq!(omitted[0].relative_path, PathBuf::from("big.rs"));
+        assert_eq!(omitted[0].reason, OmissionReason::TokenBudget);
+    }
+
+    #[test]
+    fn test_budget_keeps_duplicates_with_their_original() {
+        let mut files = vec![
+            file("a/b/original.rs", 40),
+            file("copy.rs", 0),
+            file("other.rs", 30),
+        ];
+        files[1].duplicate_of = Some(PathBuf::from("a/b/original.rs"));
+
+        let (kept, omitted) = budget(files.clone(), 75, &ContextConfig::default());
+        assert_eq!(kept.len(), 3, "copy.rs pulls in its original");
+        assert!(omitted.is_empty());
+
+        let (kept, omitted) = budget(files, 35, &ContextConfig::default());
+        let kept: Vec<_> = kept.iter().map(|f| f.relative_path.clone()).collect();
+        let reasons: Vec<_> = omitted.iter().map(|f| f.reason).collect();
+        let omitted: Vec<_> = omitted.iter().map(|f| f.relative_path.clone()).collect();
+        assert_eq!(kept, vec![PathBuf::from("other.rs")]);
+        assert_eq!(
+            omitted,
+            vec![PathBuf::from("a/b/original.rs"), PathBuf::from("copy.rs")]
+        );
+        assert_eq!(
+            reasons,
+            vec![
+                OmissionReason::TokenBudget,
+                OmissionReason::ReferenceOmitted
+            ]
+        );
+    }
+
+    #[test]
+    fn test_budget_keeps_siblings_with_their_representative() {
+        let mut files = vec![
+            file("a/b/base.toml", 40),
+            file("sibling.toml", 5),
+            file("other.rs", 30),
+        ];
+        files[1].similar_to = Some(SimilarTo {
+            path: PathBuf::from("a/b/base.toml"),
+            similarity: 0.9,
+            body: (String::new(), 40),
+        });
+
+        let (kept, omitted) = budget(files, 60, &ContextConfig::default());
+        let kept: Vec<_> = kept.iter().map(|f| f.relative_path.clone()).collect();
+        let omitted: Vec<_> = omitted.iter().map(|f| f.relative_path.clone()).collect();
+
+        assert_eq!(
+            kept,
+            vec![
+                PathBuf::from("a/b/base.toml"),
+                PathBuf::from("sibling.toml")
+            ]
+        );
+        assert_eq!(omitted, vec![PathBuf::from("other.rs")]);
+    }
+
+    #[test]
+    fn test_budget_explicit_includes_win() {
+        let config = ContextConfig {
+            include_paths: vec!["x/**/*.rs".into()],
+            ..Default::default()
+        };
+        let files = vec![file("top.rs", 50), file("x/y/deep.rs", 50)];
+
+        let (kept, omitted) = budget(files, 60, &config);
+
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].relative_path, PathBuf::from("x/y/deep.rs"));
+        assert_eq!(omitted[0].relative_path, PathBuf::from("top.rs"));
+    }
+
+    #[test]
+    fn test_budget_subtracts_overhead_and_uses_cost() {
+        let files = vec![file("a.rs", 10), file("b.rs", 10)];
+        // Each file renders to twice its token count.
+        let cost = |file: &FileContext| file.token_count * 2;
+
+        let (kept, omitted) = apply_token_budget(
+            files,
+            50,
+            20,
+            &cost,
+            &ContextConfig::default(),
+            &WordCounter,
+        );
+
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].relative_path, PathBuf::from("a.rs"));
+        assert_eq!(omitted[0].relative_path, PathBuf::from("b.rs"));
+    }
+
+    #[test]
+    fn test_budget_elides_to_skeleton_when_full_does_not_fit() {
+        let text = "fn run() {\n    let a = 1;\n    let b = 2;\n    let c = a + b;\n}\n";
+        let big = FileContext::new(
+            PathBuf::from("big.rs"),
+            PathBuf::from("big.rs"),
+            ContentType::Text(text.into()),
+            "rs".into(),
+            WordCounter.count(text),
+        );
+
+        let (kept, omitted) = budget(vec![big], 6, &ContextConfig::default());
+
+        assert!(omitted.is_empty());
+        assert_eq!(kept[0].mode, ContentMode::Skeleton);
+        assert!(kept[0].token_count <= 6);
+    }
+}
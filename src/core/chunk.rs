@@ -0,0 +1,152 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::ports::tokenizer::TokenCounter;
+
+/// Lines that open a new definition; preferred places to start a chunk.
+static DEFINITION_START: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(export\s+)?(default\s+)?(pub(\([^)]*\))?\s+)?(async\s+)?(unsafe\s+)?(fn|def|class|func|function|impl|struct|enum|trait|interface|mod)\b",
+    )
+    .unwrap()
+});
+
+/// A contiguous slice of a file prepared for embedding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChunk {
+    /// Byte offset of the first byte of the chunk.
+    pub start_byte: usize,
+    /// Byte offset one past the last byte of the chunk.
+    pub end_byte: usize,
+    /// 1-based first line.
+    pub start_line: usize,
+    /// 1-based last line (inclusive).
+    pub end_line: usize,
+    pub token_count: usize,
+    pub text: String,
+}
+
+/// Splits `text` into chunks of at most `max_tokens` whole lines, repeating up to
+/// `overlap_tokens` worth of trailing lines at the start of the next chunk.
+///
+/// When a chunk has to be cut, the cut moves back to the last blank line or
+/// definition start in its second half, so chunks tend to hold whole functions.
+/// A single line larger than `max_tokens` becomes a chunk of its own.
+pub fn chunk_text(
+    text: &str,
+    max_tokens: usize,
+    overlap_tokens: usize,
+    counter: &dyn TokenCounter,
+) -> Vec<TextChunk> {
+    let lines: Vec<(usize, &str)> = text
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .collect();
+    let line_tokens: Vec<usize> = lines.iter().map(|(_, l)| counter.count(l)).collect();
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let mut end = start;
+        let mut tokens = 0;
+        let mut best_cut = None;
+        while end < lines.len() && (end == start || tokens + line_tokens[end] <= max_tokens) {
+            tokens += line_tokens[end];
+            end += 1;
+            if end < lines.len() && is_boundary(&lines, end) {
+                best_cut = Some(end);
+            }
+        }
+        if end < lines.len() {
+            if let Some(cut) = best_cut {
+                if (cut - start) * 2 >= end - start {
+                    end = cut;
+                }
+            }
+        }
+
+        let start_byte = lines[start].0;
+        let end_byte = lines[end - 1].0 + lines[end - 1].1.len();
+        chunks.push(TextChunk {
+            start_byte,
+            end_byte,
+            start_line: start + 1,
+            end_line: end,
+            token_count: line_tokens[start..end].iter().sum(),
+            text: text[start_byte..end_byte].to_string(),
+        });
+
+        if end >= lines.len() {
+            break;
+        }
+
+        let mut next = end;
+        let mut overlap = 0;
+        while overlap_tokens > 0
+            && next > start + 1
+            && overlap + line_tokens[next - 1] <= overlap_tokens
+        {
+            overlap += line_tokens[next - 1];
+            next -= 1;
+        }
+        start = next;
+    }
+
+    chunks
+}
+
+/// A chunk may start at `index` when the previous line is blank or the line opens a definition.
+fn is_boundary(lines: &[(usize, &str)], index: usize) -> bool {
+    lines[index - 1].1.trim().is_empty() || DEFINITION_START.is_match(lines[index].1.trim_start())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts one token per whitespace-separated word.
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+
+        fn name(&self) -> &str {
+            "words"
+        }
+    }
+
+    #[test]
+    fn test_chunks_prefer_definition_boundaries() {
+        let text = "fn a() {\n    one two\n}\n\nfn b() {\n    three four\n}\n";
+        let chunks = chunk_text(text, 8, 0, &WordCounter);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].text, "fn a() {\n    one two\n}\n\n");
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (1, 4));
+        assert_eq!(chunks[1].text, "fn b() {\n    three four\n}\n");
+        assert_eq!(chunks[1].start_byte, chunks[0].end_byte);
+        assert_eq!(
+            &text[chunks[1].start_byte..chunks[1].end_byte],
+            chunks[1].text
+        );
+    }
+
+    #[test]
+    fn test_chunks_overlap() {
+        let text: String = (1..=10).map(|i| format!("w{}\n", i)).collect();
+        let chunks = chunk_text(&text, 4, 1, &WordCounter);
+
+        assert_eq!(chunks[0].text, "w1\nw2\nw3\nw4\n");
+        assert_eq!(
+            chunks[1].start_line, 4,
+            "Last line of the previous chunk is repeated"
+        );
+        assert_eq!(chunks.last().unwrap().end_line, 10);
+    }
+}
@@ -0,0 +1,230 @@
+use similar::TextDiff;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use crate::core::content::{ContentMode, ContentType, FileContext, SimilarTo};
+use crate::core::report::SimilarStats;
+use crate::ports::tokenizer::TokenCounter;
+
+/// Number of MinHash permutations per file.
+const SIGNATURE_LEN: usize = 64;
+/// Slack allowed between the MinHash estimate and the threshold before the
+/// exact similarity is computed.
+const ESTIMATE_SLACK: f64 = 0.15;
+/// Files with fewer distinct lines are cheaper to repeat than to diff.
+const MIN_LINES: usize = 3;
+/// Unchanged lines kept around each hunk of a sibling's diff.
+const DIFF_CONTEXT: usize = 2;
+
+/// Distinct trimmed lines of a file plus their MinHash signature.
+struct Fingerprint {
+    lines: HashSet<u64>,
+    signature: [u64; SIGNATURE_LEN],
+}
+
+impl Fingerprint {
+    fn new(text: &str) -> Self {
+        let lines: HashSet<u64> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(hash_line)
+            .collect();
+
+        let mut signature = [u64::MAX; SIGNATURE_LEN];
+        for &line in &lines {
+            for (seed, slot) in signature.iter_mut().enumerate() {
+                *slot = (*slot).min(mix(line ^ (seed as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)));
+            }
+        }
+        Self { lines, signature }
+    }
+
+    fn estimate(&self, other: &Self) -> f64 {
+        let equal = self
+            .signature
+            .iter()
+            .zip(&other.signature)
+            .filter(|(a, b)| a == b)
+            .count();
+        equal as f64 / SIGNATURE_LEN as f64
+    }
+
+    /// Jaccard similarity of the two line sets.
+    fn similarity(&self, other: &Self) -> f64 {
+        let shared = self.lines.intersection(&other.lines).count();
+        let union = self.lines.len() + other.lines.len() - shared;
+        shared as f64 / union as f64
+    }
+}
+
+fn hash_line(line: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// SplitMix64 finalizer, used to derive the MinHash permutations.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Groups near-identical text files and replaces the body of every file but
+/// the first of each group with a unified diff against it, in `diff`.
+///
+/// Files are compared only with files of the same language. The first file of a
+/// cluster is its representative and keeps its body; a sibling joins when the
+/// Jaccard similarity of their line sets is at least `threshold`, and is only
+/// collapsed when its diff costs fewer tokens than its body.
+pub fn collapse_similar(
+    files: &mut [FileContext],
+    threshold: f64,
+    counter: &dyn TokenCounter,
+) -> SimilarStats {
+    let fingerprints: Vec<Option<Fingerprint>> = files
+        .iter()
+        .map(|file| match &file.content {
+            // Files already carrying a `--diff` patch keep it.
+            ContentType::Text(text)
+                if file.duplicate_of.is_none() && file.chunk.is_none() && file.diff.is_none() =>
+            {
+                Some(Fingerprint::new(text)).filter(|f| f.lines.len() >= MIN_LINES)
+            }
+            _ => None,
+        })
+        .collect();
+
+    // (sibling, representative, similarity)
+    let mut matches = Vec::new();
+    let mut representatives: Vec<usize> = Vec::new();
+    for (i, fingerprint) in fingerprints.iter().enumerate() {
+        let Some(fingerprint) = fingerprint else {
+            continue;
+        };
+        let found = representatives.iter().find_map(|&r| {
+            let candidate = fingerprints[r].as_ref()?;
+            if files[r].language != files[i].language
+                || candidate.estimate(fingerprint) < threshold - ESTIMATE_SLACK
+            {
+                return None;
+            }
+            let similarity = candidate.similarity(fingerprint);
+            (similarity >= threshold).then_some((r, similarity))
+        });
+        match found {
+            Some((r, similarity)) => matches.push((i, r, similarity)),
+            None => representatives.push(i),
+        }
+    }
+
+    let mut stats = SimilarStats::default();
+    let mut clustered = HashSet::new();
+    for (i, r, similarity) in matches {
+        let (ContentType::Text(base), ContentType::Text(text)) =
+            (&files[r].content, &files[i].content)
+        else {
+            continue;
+        };
+        let diff = TextDiff::from_lines(base, text)
+            .unified_diff()
+            .context_radius(DIFF_CONTEXT)
+            .header(
+                &files[r].relative_path.to_string_lossy(),
+                &files[i].relative_path.to_string_lossy(),
+            )
+            .to_string();
+        let diff_tokens = counter.count(&diff);
+        if diff_tokens >= files[i].token_count {
+            continue;
+        }
+
+        let path = files[r].relative_path.clone();
+        let file = &mut files[i];
+        let ContentType::Text(text) = &mut file.content else {
+            continue;
+        };
+        // The body moves into `similar_to`, leaving an empty one behind.
+        let body = (std::mem::take(text), file.token_count);
+        stats.files += 1;
+        stats.tokens_saved += file.token_count - diff_tokens;
+        file.diff = Some(diff);
+        file.mode = ContentMode::Diff;
+        file.token_count = diff_tokens;
+        file.similar_to = Some(SimilarTo {
+            path,
+            similarity,
+            body,
+        });
+        clustered.insert(r);
+    }
+    stats.clusters = clustered.len();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct LineCounter;
+
+    impl TokenCounter for LineCounter {
+        fn count(&self, text: &str) -> usize {
+            text.lines().count() * 4
+        }
+
+        fn name(&self) -> &str {
+            "lines"
+        }
+    }
+
+    fn file(path: &str, language: &str, text: &str) -> FileContext {
+        FileContext::new(
+            PathBuf::from(path),
+            PathBuf::from(path),
+            ContentType::Text(text.into()),
+            language.into(),
+            LineCounter.count(text),
+        )
+    }
+
+    #[test]
+    fn test_collapse_near_duplicates() {
+        let base: String = (1..=20)
+            .map(|i| format!("key_{} = value_{}\n", i, i))
+            .collect();
+        let variant = base.replace("value_7\n", "changed\n");
+        let mut files = vec![
+            file("locales/en.toml", "toml", &base),
+            file("locales/es.toml", "toml", &variant),
+            file("other.toml", "toml", "a = 1\nb = 2\nc = 3\n"),
+            file("copy.ini", "ini", &base),
+        ];
+
+        let stats = collapse_similar(&mut files, 0.8, &LineCounter);
+
+        assert_eq!((stats.clusters, stats.files), (1, 1));
+        assert!(stats.tokens_saved > 0);
+
+        let sibling = &files[1];
+        let similar_to = sibling.similar_to.as_ref().unwrap();
+        assert_eq!(similar_to.path, PathBuf::from("locales/en.toml"));
+        assert!(similar_to.similarity >= 0.8 && similar_to.similarity < 1.0);
+        assert_eq!(
+            (sibling.language.as_str(), sibling.mode),
+            ("toml", ContentMode::Diff)
+        );
+        let diff = sibling.diff.as_deref().expect("Diff expected");
+        assert!(diff.contains("-key_7 = value_7\n+key_7 = changed\n"));
+        assert!(diff.starts_with("--- locales/en.toml\n+++ locales/es.toml\n"));
+
+        assert!(files[2].similar_to.is_none());
+        assert!(
+            files[3].similar_to.is_none(),
+            "Only files of the same language are compared"
+        );
+    }
+}
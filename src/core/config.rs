@@ -1,19 +1,171 @@
+use chrono::{DateTime, Utc};
 use clap::ValueEnum;
-use std::collections::HashSet;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::noise::NoiseRules;
+
+/// Default target size of a `jsonl-chunks` chunk, in tokens.
+pub const DEFAULT_CHUNK_TOKENS: usize = 512;
+/// Default overlap between consecutive `jsonl-chunks` chunks, in tokens.
+pub const DEFAULT_CHUNK_OVERLAP: usize = 64;
+/// Default unchanged lines around each `--diff` hunk, as in `git diff`.
+pub const DEFAULT_DIFF_CONTEXT: u32 = 3;
 
 /// Enum defining available output formats.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
 pub enum OutputFormat {
+    #[default]
     Xml,
     Markdown,
     Json,
     Text,
+    /// One JSON object per content chunk (JSONL), for RAG ingestion.
+    JsonlChunks,
 }
 
-impl Default for OutputFormat {
-    fn default() -> Self {
-        Self::Xml
+impl OutputFormat {
+    /// File extension conventionally used for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Xml => "xml",
+            Self::Markdown => "md",
+            Self::Json => "json",
+            Self::Text => "txt",
+            Self::JsonlChunks => "jsonl",
+        }
+    }
+}
+
+/// What to do with text files larger than `--max-file-size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OversizePolicy {
+    /// Leave the body out and record why.
+    #[default]
+    Skip,
+    /// Keep the first `--max-file-size` bytes.
+    Truncate,
+}
+
+/// Files added to a git selection besides the changed ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RelatedFiles {
+    /// Every file in the directory of a changed file.
+    Siblings,
+    /// Files whose import lines mention a changed file's module.
+    Importers,
+}
+
+/// How files get rendered in `--diff` mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DiffMode {
+    /// Only the changed lines, without context or body.
+    Only,
+    /// The diff with `--diff-context` lines around each hunk, without body.
+    Context,
+    /// The diff followed by the whole new file.
+    #[default]
+    Full,
+}
+
+/// Restricts the scan to the files touched in the local git repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitSelection {
+    /// Files changed between this revision and the working tree.
+    pub changed_since: Option<String>,
+    /// Files in the index that differ from `HEAD`.
+    pub staged: bool,
+    /// Staged, unstaged and untracked files.
+    pub uncommitted: bool,
+    /// Files changed in this `--diff` range (`A..B`, `A...B`, or `A` against the working tree).
+    pub diff_range: Option<String>,
+    pub related: Vec<RelatedFiles>,
+}
+
+impl GitSelection {
+    pub fn is_active(&self) -> bool {
+        self.changed_since.is_some() || self.staged || self.uncommitted || self.diff_range.is_some()
+    }
+}
+
+/// Restricts the scan by modification time, from the file system or, with
+/// `git_mtime`, from the last commit touching each file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeFilter {
+    pub newer_than: Option<DateTime<Utc>>,
+    pub older_than: Option<DateTime<Utc>>,
+    pub git_mtime: bool,
+}
+
+impl TimeFilter {
+    /// Whether modification times are recorded (and maybe filtered on).
+    pub fn is_active(&self) -> bool {
+        self.newer_than.is_some() || self.older_than.is_some() || self.git_mtime
+    }
+
+    pub fn matches(&self, modified: DateTime<Utc>) -> bool {
+        self.newer_than.map_or(true, |since| modified >= since)
+            && self.older_than.map_or(true, |until| modified < until)
+    }
+}
+
+/// BPE encodings available for token counting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TokenizerKind {
+    /// GPT-4 / GPT-3.5 encoding.
+    Cl100k,
+    /// GPT-4o and newer encoding.
+    #[default]
+    O200k,
+}
+
+impl TokenizerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cl100k => "cl100k",
+            Self::O200k => "o200k",
+        }
+    }
+}
+
+/// One of several project roots scanned into a single report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectRoot {
+    /// Unique name prefixed to the relative paths of the root's files.
+    pub label: String,
+    pub path: PathBuf,
+    /// Noise rules from the root's own `.context.toml`.
+    #[serde(skip)]
+    pub noise: NoiseRules,
+}
+
+impl ProjectRoot {
+    /// Labels each path with its directory name, numbering repeated names
+    /// (`src`, `src-2`).
+    pub fn label_all(paths: &[PathBuf]) -> Vec<Self> {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        paths
+            .iter()
+            .map(|path| {
+                let name = fs::canonicalize(path)
+                    .ok()
+                    .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+                    .unwrap_or_else(|| "root".to_string());
+                let count = seen.entry(name.clone()).or_default();
+                *count += 1;
+                let label = match *count {
+                    1 => name,
+                    n => format!("{}-{}", name, n),
+                };
+                Self {
+                    label,
+                    path: path.clone(),
+                    noise: NoiseRules::default(),
+                }
+            })
+            .collect()
     }
 }
 
@@ -33,6 +185,33 @@ pub struct ContextConfig {
     pub exclude_extensions: HashSet<String>,
     pub include_paths: Vec<String>,
     pub exclude_paths: Vec<String>,
+    pub tokenizer: TokenizerKind,
+    pub max_tokens: Option<usize>,
+    pub max_file_tokens: Option<usize>,
+    pub split_tokens: Option<usize>,
+    pub chunk_tokens: usize,
+    pub chunk_overlap: usize,
+    pub strip_comments: bool,
+    pub keep_doc_comments: bool,
+    pub skeleton: bool,
+    pub focus: Vec<String>,
+    pub dedup: bool,
+    pub collapse_similar: Option<f64>,
+    pub ignore_files: Vec<PathBuf>,
+    pub noise: NoiseRules,
+    pub max_file_size: Option<u64>,
+    pub oversize: OversizePolicy,
+    pub normalize_eol: bool,
+    pub follow_symlinks: bool,
+    pub git: GitSelection,
+    pub diff_mode: DiffMode,
+    pub diff_context: u32,
+    pub rev: Option<String>,
+    pub grep: Option<String>,
+    pub grep_context: Option<usize>,
+    pub time: TimeFilter,
+    /// Every root when several are given; empty for a single root (`root_path`).
+    pub roots: Vec<ProjectRoot>,
 }
 
 impl ContextConfig {
@@ -71,6 +250,69 @@ impl ContextConfig {
             exclude_extensions,
             include_paths,
             exclude_paths,
+            tokenizer: TokenizerKind::default(),
+            max_tokens: None,
+            max_file_tokens: None,
+            split_tokens: None,
+            chunk_tokens: DEFAULT_CHUNK_TOKENS,
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+            strip_comments: false,
+            keep_doc_comments: false,
+            skeleton: false,
+            focus: Vec::new(),
+            dedup: true,
+            collapse_similar: None,
+            ignore_files: Vec::new(),
+            noise: NoiseRules::default(),
+            max_file_size: None,
+            oversize: OversizePolicy::Skip,
+            normalize_eol: false,
+            follow_symlinks: false,
+            git: GitSelection::default(),
+            diff_mode: DiffMode::Full,
+            diff_context: DEFAULT_DIFF_CONTEXT,
+            rev: None,
+            grep: None,
+            grep_context: None,
+            time: TimeFilter::default(),
+            roots: Vec::new(),
+        }
+    }
+}
+
+impl ContextConfig {
+    /// True when file bodies are rewritten before being emitted (see `render_text`).
+    pub fn transforms_text(&self) -> bool {
+        self.minify || self.strip_comments
+    }
+
+    /// The configuration for scanning a single one of several roots.
+    pub fn for_root(&self, root: &ProjectRoot) -> Self {
+        Self {
+            root_path: root.path.clone(),
+            roots: Vec::new(),
+            noise: root.noise.clone(),
+            ..self.clone()
+        }
+    }
+
+    /// Each root with the label prefixed to its relative paths (`None` for a single root).
+    pub fn root_paths(&self) -> Vec<(Option<&str>, &Path)> {
+        if self.roots.is_empty() {
+            return vec![(None, self.root_path.as_path())];
+        }
+        self.roots
+            .iter()
+            .map(|root| (Some(root.label.as_str()), root.path.as_path()))
+            .collect()
+    }
+
+    /// Name printed at the top of the directory tree. With several roots the
+    /// tree starts at `.` and each root label is a top-level entry.
+    pub fn root_name(&self) -> String {
+        match self.root_path.file_name() {
+            Some(name) if self.roots.is_empty() => name.to_string_lossy().into_owned(),
+            _ => ".".to_string(),
         }
     }
 }
@@ -91,6 +333,32 @@ impl Default for ContextConfig {
             exclude_extensions: HashSet::new(),
             include_paths: Vec::new(),
             exclude_paths: Vec::new(),
+            tokenizer: TokenizerKind::default(),
+            max_tokens: None,
+            max_file_tokens: None,
+            split_tokens: None,
+            chunk_tokens: DEFAULT_CHUNK_TOKENS,
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+            strip_comments: false,
+            keep_doc_comments: false,
+            skeleton: false,
+            focus: Vec::new(),
+            dedup: true,
+            collapse_similar: None,
+            ignore_files: Vec::new(),
+            noise: NoiseRules::default(),
+            max_file_size: None,
+            oversize: OversizePolicy::Skip,
+            normalize_eol: false,
+            follow_symlinks: false,
+            git: GitSelection::default(),
+            diff_mode: DiffMode::Full,
+            diff_context: DEFAULT_DIFF_CONTEXT,
+            rev: None,
+            grep: None,
+            grep_context: None,
+            time: TimeFilter::default(),
+            roots: Vec::new(),
         }
     }
 }
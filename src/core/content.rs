@@ -1,5 +1,12 @@
+use chrono::{DateTime, Utc};
 use serde::Serialize;
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::core::config::ContextConfig;
+use crate::core::file::{format_size, LineRange};
+use crate::core::lexer::{lex, LangFamily, SegmentKind};
+use crate::ports::tokenizer::TokenCounter;
 
 /// Enum representing the type of content found in a file.
 #[derive(Debug, Clone, PartialEq, Serialize)]
@@ -8,6 +15,113 @@ pub enum ContentType {
     Text(String),
     Binary,
     Error(String),
+    /// The file was deliberately not read.
+    Skipped(SkipReason),
+}
+
+/// Why a file's content was not read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Larger than `--max-file-size` (and not truncated).
+    TooLarge { size: u64, limit: u64 },
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::TooLarge { size, limit } => write!(
+                f,
+                "file too large ({} > {})",
+                format_size(*size),
+                format_size(*limit)
+            ),
+        }
+    }
+}
+
+/// Position of a piece of an oversized file that was split across report parts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ChunkInfo {
+    pub index: usize,
+    pub total: usize,
+}
+
+/// Representative of the near-duplicate cluster a file was collapsed into.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimilarTo {
+    pub path: PathBuf,
+    /// Jaccard similarity of the two files' line sets, in `0.0..=1.0`.
+    pub similarity: f64,
+    /// The sibling's own body and its tokens, for when it has to be emitted
+    /// away from its representative.
+    #[serde(skip)]
+    pub body: (String, usize),
+}
+
+/// Region of a file kept by `--grep-context`; lines are 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Excerpt {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    /// Byte offset of the excerpt in the file.
+    #[serde(skip)]
+    pub start_byte: usize,
+}
+
+/// How much of a file's body the report carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentMode {
+    #[default]
+    Full,
+    /// Signatures, type definitions and doc comments only (`--skeleton`).
+    Skeleton,
+    /// No body: the file is represented by its `diff` alone (a `--diff` patch,
+    /// or the diff against a near-duplicate).
+    Diff,
+    /// No body: only the regions matching `--grep`, as excerpts.
+    Excerpts,
+}
+
+impl ContentMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentMode::Full => "full",
+            ContentMode::Skeleton => "skeleton",
+            ContentMode::Diff => "diff",
+            ContentMode::Excerpts => "excerpts",
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        *self == ContentMode::Full
+    }
+}
+
+/// Level of detail a file gets when `--focus` splits the report into tiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fidelity {
+    /// Focus files: the whole body.
+    Full,
+    /// Signatures, or headings for Markdown.
+    Outline,
+    /// Path only, no body.
+    Listed,
+}
+
+impl Fidelity {
+    pub const ALL: [Fidelity; 3] = [Fidelity::Full, Fidelity::Outline, Fidelity::Listed];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Fidelity::Full => "full",
+            Fidelity::Outline => "outline",
+            Fidelity::Listed => "listed",
+        }
+    }
 }
 
 /// Domain entity representing a processed file with its content and metadata.
@@ -18,6 +132,52 @@ pub struct FileContext {
     pub content: ContentType,
     pub language: String,
     pub token_count: usize,
+    /// True when the body was cut down: to its head by `--oversize truncate`, or
+    /// to its head and tail (lines or characters) by `--max-file-tokens`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Set when this entry is one chunk of a file split by `--split-tokens`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk: Option<ChunkInfo>,
+    #[serde(skip_serializing_if = "ContentMode::is_full")]
+    pub mode: ContentMode,
+    /// Tier assigned by `--focus`; `None` when no focus is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fidelity: Option<Fidelity>,
+    /// Path of an earlier file with identical content; the body is then omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<PathBuf>,
+    /// Set by `--collapse-similar`; the body is then a unified diff against `path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub similar_to: Option<SimilarTo>,
+    /// Source encoding of a text file transcoded to UTF-8 (e.g. `windows-1252`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Real path of a file reached through a symlink.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<PathBuf>,
+    /// Unified diff of the file over the `--diff` range, or against the
+    /// representative of its `--collapse-similar` cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    /// Label of the file's root when several roots are scanned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    /// Line range the body was cut to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<LineRange>,
+    /// Byte offset in the file where the body starts, when only `lines` were read.
+    #[serde(skip)]
+    pub start_byte: usize,
+    /// Item selected with `path#symbol`; `lines` holds where it was found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Modification time (file system or last commit) under a time filter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
+    /// Matching regions kept by `--grep-context`, in file order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excerpts: Vec<Excerpt>,
 }
 
 impl FileContext {
@@ -34,25 +194,181 @@ impl FileContext {
             content,
             language,
             token_count,
+            truncated: false,
+            chunk: None,
+            mode: ContentMode::Full,
+            fidelity: None,
+            duplicate_of: None,
+            similar_to: None,
+            encoding: None,
+            link_target: None,
+            diff: None,
+            root: None,
+            lines: None,
+            start_byte: 0,
+            symbol: None,
+            modified: None,
+            excerpts: Vec::new(),
+        }
+    }
+
+    /// Listed files keep their entry in the report but carry no body.
+    pub fn is_listed(&self) -> bool {
+        self.fidelity == Some(Fidelity::Listed)
+    }
+
+    /// Recounts `token_count` on the text the writers emit, after
+    /// `--strip-comments` and `--minify`. Diffs are emitted unchanged.
+    pub fn count_rendered(&mut self, config: &ContextConfig, counter: &dyn TokenCounter) {
+        let ContentType::Text(text) = &self.content else {
+            return;
+        };
+        let language = self.language.as_str();
+        let rendered = |text: &str| {
+            if text.is_empty() {
+                0
+            } else {
+                counter.count(&render_text(text, language, config))
+            }
+        };
+        let body = rendered(text);
+        let excerpts: usize = self.excerpts.iter().map(|e| rendered(&e.text)).sum();
+        let diff = self.diff.as_deref().map_or(0, |diff| counter.count(diff));
+        if let Some(similar_to) = &mut self.similar_to {
+            similar_to.body.1 = rendered(&similar_to.body.0);
         }
+        self.token_count = body + excerpts + diff;
+    }
+
+    /// Path relative to the file's own root, without the root label that
+    /// prefixes `relative_path` when several roots are scanned.
+    pub fn root_relative_path(&self) -> &Path {
+        match &self.root {
+            Some(root) => self
+                .relative_path
+                .strip_prefix(root)
+                .unwrap_or(&self.relative_path),
+            None => &self.relative_path,
+        }
+    }
+}
+
+/// Applies the body transforms requested in the config: comment stripping, then
+/// minification. Every writer renders text content through this function.
+pub fn render_text(text: &str, language: &str, config: &ContextConfig) -> String {
+    let mut rendered = if config.strip_comments {
+        strip_comments(text, language, config.keep_doc_comments)
+    } else {
+        text.to_string()
+    };
+    if config.minify {
+        rendered = minify_content(&rendered, language);
+    }
+    rendered
+}
+
+/// Removes line and block comments while leaving string literals untouched.
+/// Lines that only held a comment are dropped. Languages without a lexer are
+/// returned unchanged.
+pub fn strip_comments(content: &str, language: &str, keep_doc_comments: bool) -> String {
+    let Some(family) = LangFamily::from_language(language) else {
+        return content.to_string();
+    };
+
+    let bytes = content.as_bytes();
+    let mut stripped = String::with_capacity(content.len());
+    let mut cursor = 0;
+
+    for segment in lex(content, family) {
+        let removable = match segment.kind {
+            SegmentKind::Comment => true,
+            SegmentKind::DocComment => !keep_doc_comments,
+            SegmentKind::Code | SegmentKind::Literal => false,
+        };
+        if !removable {
+            continue;
+        }
+
+        let (start, end) = (segment.range.start, segment.range.end);
+        let line_start = content[..start].rfind('\n').map_or(0, |p| p + 1);
+        let line_end = content[end..].find('\n').map_or(content.len(), |p| end + p);
+        let before = &content[line_start.max(cursor)..start];
+        let after_blank = content[end..line_end].trim().is_empty();
+
+        let (cut_start, cut_end, replacement) = if after_blank && before.trim().is_empty() {
+            // Comment-only line(s): drop them entirely.
+            (line_start, (line_end + 1).min(content.len()), "")
+        } else if after_blank {
+            // Trailing comment: drop it together with the whitespace before it.
+            (
+                start - (before.len() - before.trim_end().len()),
+                line_end,
+                "",
+            )
+        } else {
+            // Inline block comment: keep the tokens around it apart.
+            let glued = start > 0
+                && !bytes[start - 1].is_ascii_whitespace()
+                && !bytes[end].is_ascii_whitespace();
+            (start, end, if glued { " " } else { "" })
+        };
+
+        let cut_start = cut_start.max(cursor);
+        stripped.push_str(&content[cursor..cut_start]);
+        stripped.push_str(replacement);
+        cursor = cut_end.max(cursor);
     }
+    stripped.push_str(&content[cursor..]);
+    stripped
 }
 
 /// Aggressively reduces content size.
+///
+/// Trailing spaces, blank lines and (outside indentation-sensitive languages)
+/// indentation are removed, except inside multi-line literals and block
+/// comments, whose bytes are kept intact for languages with a lexer.
 pub fn minify_content(content: &str, language: &str) -> String {
     let indent_sensitive = ["py", "python", "yaml", "yml", "md", "markdown"];
 
     let is_sensitive = indent_sensitive.contains(&language.to_lowercase().as_str());
+    let protected = multiline_spans(content, language);
 
-    let lines = content.lines();
     let mut minified = String::with_capacity(content.len());
+    let mut offset = 0;
+    let mut next_span = 0;
+
+    for raw_line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += raw_line.len();
+        let line = raw_line.strip_suffix('\n').unwrap_or(raw_line);
+        let line_end = line_start + line.len();
+
+        // Spans are sorted and disjoint: skip the ones that closed before this line.
+        while next_span < protected.len() && protected[next_span].end <= line_start {
+            next_span += 1;
+        }
+        // A line that starts inside a literal is literal content; a line whose
+        // newline is inside one must keep its trailing bytes.
+        let starts_inside = protected
+            .get(next_span)
+            .is_some_and(|r| r.start < line_start);
+        let ends_inside = protected[next_span..]
+            .iter()
+            .take_while(|r| r.start <= line_end)
+            .any(|r| line_end < r.end);
+
+        if starts_inside {
+            let kept = if ends_inside { line } else { line.trim_end() };
+            minified.push_str(kept);
+            minified.push('\n');
+            continue;
+        }
 
-    for line in lines {
         // 1. Remove Trailing Spaces (Manual trim is faster than Regex for single line)
-        let trimmed_end = line.trim_end();
+        let trimmed_end = if ends_inside { line } else { line.trim_end() };
 
         // 2. Remove Empty Lines
-        if trimmed_end.is_empty() {
+        if trimmed_end.trim_start().is_empty() {
             continue;
         }
 
@@ -70,6 +386,18 @@ pub fn minify_content(content: &str, language: &str) -> String {
     minified
 }
 
+/// Byte ranges of literals and block comments that span more than one line.
+fn multiline_spans(content: &str, language: &str) -> Vec<std::ops::Range<usize>> {
+    let Some(family) = LangFamily::from_language(language) else {
+        return Vec::new();
+    };
+    lex(content, family)
+        .into_iter()
+        .filter(|s| s.kind != SegmentKind::Code && content[s.range.clone()].contains('\n'))
+        .map(|s| s.range)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,4 +417,146 @@ fn main() {
         let result = minify_content(input, "rs");
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_strip_comments_rust() {
+        let input = r##"//! Crate docs.
+/// Adds one.
+fn add(x: i32) -> i32 {
+    // increment
+    let s = "// not a comment"; /* inline */ let r = r#"/* raw */"#;
+    x /* keep apart */+ 1 // trailing
+}
+"##;
+        let expected = r##"fn add(x: i32) -> i32 {
+    let s = "// not a comment";  let r = r#"/* raw */"#;
+    x + 1
+}
+"##;
+        assert_eq!(strip_comments(input, "rs", false), expected);
+
+        let with_docs = strip_comments(input, "rs", true);
+        assert!(with_docs.starts_with("//! Crate docs.\n/// Adds one.\nfn add"));
+        assert!(!with_docs.contains("increment"));
+    }
+
+    #[test]
+    fn test_strip_comments_other_languages() {
+        let js = "const re = /\\/\\//; // slashes\nconst t = `// ${a /* x */}`;\n";
+        assert_eq!(
+            strip_comments(js, "ts", false),
+            "const re = /\\/\\//;\nconst t = `// ${a /* x */}`;\n"
+        );
+
+        let py = "def f():\n    \"\"\"Doc # kept.\"\"\"\n    return '#'  # comment\n";
+        assert_eq!(
+            strip_comments(py, "py", false),
+            "def f():\n    \"\"\"Doc # kept.\"\"\"\n    return '#'\n"
+        );
+
+        let sh = "#!/bin/sh\n# setup\necho \"#1\" $# # count\n";
+        assert_eq!(
+            strip_comments(sh, "sh", false),
+            "#!/bin/sh\necho \"#1\" $#\n"
+        );
+
+        let sql = "SELECT '--x' -- pick\nFROM t; /* done */\n";
+        assert_eq!(strip_comments(sql, "sql", false), "SELECT '--x'\nFROM t;\n");
+
+        let go = "// Run starts it.\nfunc Run() {\n\t// body\n\ts := `//raw`\n}\n";
+        assert_eq!(
+            strip_comments(go, "go", true),
+            "// Run starts it.\nfunc Run() {\n\ts := `//raw`\n}\n"
+        );
+
+        let java = "String s = \"/* no */\"; /** Doc */ int x; // c\n";
+        assert_eq!(
+            strip_comments(java, "java", false),
+            "String s = \"/* no */\";  int x;\n"
+        );
+    }
+
+    #[test]
+    fn test_minify_preserves_rust_literals() {
+        let input = "fn q() -> &'static str {\n    let sql = r#\"\n        SELECT *\n\n        FROM t   \n    \"#;\n    /** Example:\n     *\n     *     indented code\n     */\n    sql\n}\n";
+        let expected = "fn q() -> &'static str {\nlet sql = r#\"\n        SELECT *\n\n        FROM t   \n    \"#;\n/** Example:\n     *\n     *     indented code\n     */\nsql\n}\n";
+        assert_eq!(minify_content(input, "rs"), expected);
+    }
+
+    #[test]
+    fn test_minify_preserves_js_template_literals() {
+        let input = "function html() {\n    return `\n        <ul>\n\n            <li>${item}</li>\n        </ul>`;\n}\n";
+        let expected = "function html() {\nreturn `\n        <ul>\n\n            <li>${item}</li>\n        </ul>`;\n}\n";
+        assert_eq!(minify_content(input, "js"), expected);
+    }
+
+    #[test]
+    fn test_minify_preserves_shell_heredocs() {
+        let input = "main() {\n    cat <<-EOF\n\t  indented\n\n\tEOF\n    echo done\n}\n";
+        let expected = "main() {\ncat <<-EOF\n\t  indented\n\n\tEOF\necho done\n}\n";
+        assert_eq!(minify_content(input, "sh"), expected);
+    }
+
+    #[test]
+    fn test_minify_preserves_python_strings() {
+        let input = "def f():\n    q = \"\"\"\n    SELECT 1\n\n    \"\"\"\n\n    return q\n";
+        let expected = "def f():\n    q = \"\"\"\n    SELECT 1\n\n    \"\"\"\n    return q\n";
+        assert_eq!(minify_content(input, "py"), expected);
+    }
+
+    #[test]
+    fn test_minify_preserves_sql_in_string_constants() {
+        let java = "class Q {\n    String q = \"\"\"\n        SELECT a\n          FROM b\n        \"\"\";\n}\n";
+        let expected_java = "class Q {\nString q = \"\"\"\n        SELECT a\n          FROM b\n        \"\"\";\n}\n";
+        assert_eq!(minify_content(java, "java"), expected_java);
+
+        let go = "func q() string {\n\treturn `\n\t\tSELECT a\n\n\t\tFROM b`\n}\n";
+        let expected_go = "func q() string {\nreturn `\n\t\tSELECT a\n\n\t\tFROM b`\n}\n";
+        assert_eq!(minify_content(go, "go"), expected_go);
+
+        let sql = "CREATE FUNCTION f() RETURNS text AS $$\n    SELECT 'a'\n\n    FROM t\n$$ LANGUAGE sql;\n";
+        let expected_sql = "CREATE FUNCTION f() RETURNS text AS $$\n    SELECT 'a'\n\n    FROM t\n$$ LANGUAGE sql;\n";
+        assert_eq!(minify_content(sql, "sql"), expected_sql);
+    }
+
+    #[test]
+    fn test_minify_preserves_cpp_raw_strings() {
+        let input = "auto s = R\"sql(\n    SELECT 1;\n\n)sql\";\n    int x;\n";
+        let expected = "auto s = R\"sql(\n    SELECT 1;\n\n)sql\";\nint x;\n";
+        assert_eq!(minify_content(input, "cpp"), expected);
+    }
+
+    #[test]
+    fn test_count_rendered_after_strip_comments() {
+        struct WordCounter;
+
+        impl TokenCounter for WordCounter {
+            fn count(&self, text: &str) -> usize {
+                text.split_whitespace().count()
+            }
+
+            fn name(&self) -> &str {
+                "words"
+            }
+        }
+
+        let text = "// a long comment here\nfn a() {}\n";
+        let mut file = FileContext::new(
+            PathBuf::from("a.rs"),
+            PathBuf::from("a.rs"),
+            ContentType::Text(text.into()),
+            "rs".into(),
+            WordCounter.count(text),
+        );
+        file.diff = Some("+ x".into());
+        let config = ContextConfig {
+            strip_comments: true,
+            ..Default::default()
+        };
+
+        file.count_rendered(&config, &WordCounter);
+
+        // `fn a() {}` plus the diff, which is emitted as is.
+        assert_eq!(file.token_count, 3 + 2);
+    }
 }
@@ -0,0 +1,112 @@
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::core::content::{ContentMode, ContentType, Fidelity, FileContext};
+use crate::core::report::DuplicateStats;
+
+/// Marks text files whose content is byte-identical to an earlier file.
+///
+/// The first occurrence keeps its body; later copies get `duplicate_of` set to
+/// its path and lose their body and tokens. Blank files are never treated as
+/// duplicates, since referencing them saves nothing. Only bodies still holding
+/// the whole file are compared: a skeleton, excerpt or cut-down body says
+/// nothing about the rest of the file.
+pub fn dedupe_files(files: &mut [FileContext]) -> DuplicateStats {
+    let mut seen: HashMap<[u8; 32], PathBuf> = HashMap::new();
+    let mut stats = DuplicateStats::default();
+
+    for file in files.iter_mut() {
+        if !is_verbatim(file) {
+            continue;
+        }
+        let ContentType::Text(text) = &file.content else {
+            continue;
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        match seen.entry(Sha256::digest(text.as_bytes()).into()) {
+            Entry::Vacant(entry) => {
+                entry.insert(file.relative_path.clone());
+            }
+            Entry::Occupied(entry) => {
+                stats.files += 1;
+                stats.tokens_saved += file.token_count;
+                file.duplicate_of = Some(entry.get().clone());
+                file.content = ContentType::Text(String::new());
+                file.token_count = 0;
+            }
+        }
+    }
+
+    stats
+}
+
+/// True when the body is the file as read, untouched by `--skeleton`,
+/// `--focus`, `--max-file-tokens`, `--grep-context`, `--diff` or a fragment.
+fn is_verbatim(file: &FileContext) -> bool {
+    file.mode == ContentMode::Full
+        && file.fidelity.map_or(true, |f| f == Fidelity::Full)
+        && !file.truncated
+        && file.excerpts.is_empty()
+        && file.lines.is_none()
+        && file.diff.is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, text: &str, tokens: usize) -> FileContext {
+        FileContext::new(
+            PathBuf::from(path),
+            PathBuf::from(path),
+            ContentType::Text(text.into()),
+            "rs".into(),
+            tokens,
+        )
+    }
+
+    #[test]
+    fn test_dedupe_references_first_copy() {
+        let mut files = vec![
+            file("a/x.rs", "fn x() {}\n", 5),
+            file("a/y.rs", "fn y() {}\n", 5),
+            file("b/x.rs", "fn x() {}\n", 5),
+            file("a/__init__.py", "", 0),
+            file("b/__init__.py", "", 0),
+        ];
+
+        let stats = dedupe_files(&mut files);
+
+        assert_eq!(
+            stats,
+            DuplicateStats {
+                files: 1,
+                tokens_saved: 5
+            }
+        );
+        assert_eq!(files[2].duplicate_of, Some(PathBuf::from("a/x.rs")));
+        assert_eq!(files[2].token_count, 0);
+        assert_eq!(files[1].duplicate_of, None);
+        assert_eq!(
+            files[4].duplicate_of, None,
+            "Blank files are not deduplicated"
+        );
+    }
+
+    #[test]
+    fn test_dedupe_skips_reduced_bodies() {
+        let mut files = vec![file("a.rs", "fn x();\n", 3), file("b.rs", "fn x();\n", 3)];
+        files[0].mode = ContentMode::Skeleton;
+        files[1].mode = ContentMode::Skeleton;
+
+        let stats = dedupe_files(&mut files);
+
+        assert_eq!(stats, DuplicateStats::default());
+        assert!(files.iter().all(|f| f.duplicate_of.is_none()));
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+
+use crate::core::content::{ContentType, Fidelity, FileContext};
+use crate::core::pattern::PathMatcher;
+use crate::core::skeleton::skeletonize;
+use crate::ports::tokenizer::TokenCounter;
+
+/// Number of files and tokens in one fidelity tier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TierStats {
+    pub fidelity: Fidelity,
+    pub files: usize,
+    pub tokens: usize,
+}
+
+/// Assigns a fidelity tier to a file and reduces its body accordingly.
+///
+/// Files matching `focus` (relative to their own root, like the include
+/// globs) keep their full text. Every other file is replaced
+/// by its outline when one can be built, and is otherwise only listed.
+pub fn apply_fidelity(
+    file: &mut FileContext,
+    focus: &PathMatcher,
+    counter: &dyn TokenCounter,
+) -> Fidelity {
+    let fidelity = if focus.is_match(file.root_relative_path()) {
+        Fidelity::Full
+    } else {
+        let outline = match &file.content {
+            ContentType::Text(text) => outline(text, &file.language),
+            _ => None,
+        };
+        match outline {
+            Some(outline) => {
+                file.token_count = counter.count(&outline);
+                file.content = ContentType::Text(outline);
+                Fidelity::Outline
+            }
+            None => {
+                if let ContentType::Text(_) = file.content {
+                    file.content = ContentType::Text(String::new());
+                }
+                file.token_count = 0;
+                Fidelity::Listed
+            }
+        }
+    };
+    file.fidelity = Some(fidelity);
+    fidelity
+}
+
+/// Signatures for source code, headings for Markdown, `None` for anything else.
+pub fn outline(text: &str, language: &str) -> Option<String> {
+    match language.to_lowercase().as_str() {
+        "md" | "markdown" => Some(markdown_headings(text)),
+        _ => skeletonize(text, language),
+    }
+}
+
+/// ATX headings (`# Title`) outside fenced code blocks.
+fn markdown_headings(text: &str) -> String {
+    let mut headings = String::new();
+    let mut in_fence = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence && is_heading(trimmed) {
+            headings.push_str(trimmed.trim_end());
+            headings.push('\n');
+        }
+    }
+    headings
+}
+
+fn is_heading(line: &str) -> bool {
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    (1..=6).contains(&level) && line[level..].starts_with([' ', '\t'])
+}
+
+/// Per-tier totals, in `full, outline, listed` order. Empty when no file has a tier.
+pub fn tier_stats(files: &[FileContext]) -> Vec<TierStats> {
+    if files.iter().all(|f| f.fidelity.is_none()) {
+        return Vec::new();
+    }
+    Fidelity::ALL
+        .iter()
+        .map(|&fidelity| {
+            let tier = files.iter().filter(|f| f.fidelity == Some(fidelity));
+            TierStats {
+                fidelity,
+                files: tier.clone().count(),
+                tokens: tier.map(|f| f.token_count).sum(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct CharCounter;
+
+    impl TokenCounter for CharCounter {
+        fn count(&self, text: &str) -> usize {
+            text.len()
+        }
+
+        fn name(&self) -> &str {
+            "chars"
+        }
+    }
+
+    fn file(path: &str, language: &str, text: &str) -> FileContext {
+        FileContext::new(
+            PathBuf::from(path),
+            PathBuf::from(path),
+            ContentType::Text(text.into()),
+            language.into(),
+            text.len(),
+        )
+    }
+
+    #[test]
+    fn test_fidelity_tiers() {
+        let focus = PathMatcher::new(&["src/core/**".to_string()]).unwrap();
+        let body = "fn run() {\n    work();\n}\n";
+        let mut files = vec![
+            file("src/core/engine.rs", "rs", body),
+            file("src/main.rs", "rs", body),
+            file(
+                "README.md",
+                "md",
+                "# Title\ntext\n```sh\n# not a heading\n```\n## Usage\n",
+            ),
+            file("Cargo.toml", "toml", "[package]\n"),
+        ];
+        for f in &mut files {
+            apply_fidelity(f, &focus, &CharCounter);
+        }
+
+        let tiers: Vec<_> = files.iter().map(|f| f.fidelity.unwrap()).collect();
+        assert_eq!(
+            tiers,
+            [
+                Fidelity::Full,
+                Fidelity::Outline,
+                Fidelity::Outline,
+                Fidelity::Listed
+            ]
+        );
+        assert_eq!(
+            files[1].content,
+            ContentType::Text("fn run() { ... }\n".into())
+        );
+        assert_eq!(
+            files[2].content,
+            ContentType::Text("# Title\n## Usage\n".into())
+        );
+        assert_eq!(files[3].token_count, 0);
+
+        let stats = tier_stats(&files);
+        assert_eq!(stats[0].tokens, body.len());
+        assert_eq!((stats[1].files, stats[2].files), (2, 1));
+    }
+
+    #[test]
+    fn test_fidelity_matches_within_each_root() {
+        let focus = PathMatcher::new(&["src/**".to_string()]).unwrap();
+        let mut labelled = file("api/src/main.rs", "rs", "fn main() {}\n");
+        labelled.root = Some("api".into());
+
+        assert_eq!(
+            apply_fidelity(&mut labelled, &focus, &CharCounter),
+            Fidelity::Full
+        );
+    }
+}
@@ -1,10 +1,135 @@
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Coarse content class, decided by the scanner from the first bytes of a file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FileKind {
+    #[default]
+    Text,
+    /// Formats with a dedicated parser (PDF, DOCX, Excel).
+    Document,
+    Binary,
+}
+
+/// 1-based, inclusive range of lines selected from a file (`path:120-260`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    /// Parses `START-END` or a single line `N`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (start, end) = spec.split_once('-').unwrap_or((spec, spec));
+        let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+        (start >= 1 && start <= end).then_some(Self { start, end })
+    }
+
+    /// The selected lines of `text`, clamped to its length.
+    pub fn slice<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.byte_range(text)]
+    }
+
+    /// Byte range of the selected lines in `text`, clamped to its length.
+    pub fn byte_range(&self, text: &str) -> Range<usize> {
+        // Byte offset where line `n` starts.
+        let line_start = |n: usize| match n {
+            1 => 0,
+            n => text
+                .match_indices('\n')
+                .nth(n - 2)
+                .map_or(text.len(), |(i, _)| i + 1),
+        };
+        line_start(self.start)..line_start(self.end.saturating_add(1))
+    }
+}
+
+impl fmt::Display for LineRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Part of a file selected instead of the whole of it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Fragment {
+    /// `path:START-END`
+    Lines(LineRange),
+    /// `path#name`: a function, type or class, located when the file is read.
+    Symbol(String),
+}
+
+impl Fragment {
+    /// Parses a fragment on its own: `START-END`, `N`, `#name` or `name`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        if let Some(range) = LineRange::parse(spec) {
+            return Some(Self::Lines(range));
+        }
+        let name = spec.strip_prefix('#').unwrap_or(spec);
+        is_symbol(name).then(|| Self::Symbol(name.to_string()))
+    }
+}
+
+/// Written as the suffix of a path spec: `:120-260` or `#name`.
+impl fmt::Display for Fragment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lines(range) => write!(f, ":{}", range),
+            Self::Symbol(name) => write!(f, "#{}", name),
+        }
+    }
+}
+
+fn is_symbol(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Splits `path:START-END` or `path#symbol` into the path and its fragment.
+/// Anything after the last `:` or `#` that is not a range or an identifier
+/// stays part of the path.
+pub fn parse_path_spec(spec: &str) -> (PathBuf, Option<Fragment>) {
+    if let Some((path, range)) = spec.rsplit_once(':') {
+        if let Some(range) = LineRange::parse(range) {
+            return (PathBuf::from(path), Some(Fragment::Lines(range)));
+        }
+    }
+    if let Some((path, name)) = spec.rsplit_once('#') {
+        if !path.is_empty() && is_symbol(name) {
+            return (
+                PathBuf::from(path),
+                Some(Fragment::Symbol(name.to_string())),
+            );
+        }
+    }
+    (PathBuf::from(spec), None)
+}
 
 /// Domain entity representing a file found in the project.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FileNode {
     pub path: PathBuf,
     pub relative_path: PathBuf,
+    /// Size in bytes, as reported by the file system.
+    pub size: u64,
+    pub kind: FileKind,
+    /// Real location of a file reached through a symlink (`--follow-symlinks`),
+    /// relative to the root when it lies inside the project.
+    pub link_target: Option<PathBuf>,
+    /// Label of the root the file belongs to when several roots are scanned;
+    /// `relative_path` then starts with it.
+    pub root: Option<String>,
+    /// Only this part of the file is read (`path:START-END`, `path#symbol`).
+    pub fragment: Option<Fragment>,
+    /// Modification time, recorded when a time filter is active.
+    pub modified: Option<DateTime<Utc>>,
 }
 
 impl FileNode {
@@ -13,6 +138,87 @@ impl FileNode {
         Self {
             path,
             relative_path,
+            size: 0,
+            kind: FileKind::Text,
+            link_target: None,
+            root: None,
+            fragment: None,
+            modified: None,
         }
     }
+
+    /// Moves the file under the root labelled `label`.
+    pub fn under_root(mut self, label: &str) -> Self {
+        self.relative_path = Path::new(label).join(&self.relative_path);
+        self.root = Some(label.to_string());
+        self
+    }
+
+    /// Sets the size and kind found by the scanner.
+    pub fn with_metadata(mut self, size: u64, kind: FileKind) -> Self {
+        self.size = size;
+        self.kind = kind;
+        self
+    }
+}
+
+/// Human-readable size with binary units (`512 B`, `1.5 KB`, `2.0 GB`).
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_spec_fragments() {
+        assert_eq!(
+            parse_path_spec("src/main.rs:2-3"),
+            (
+                PathBuf::from("src/main.rs"),
+                Some(Fragment::Lines(LineRange { start: 2, end: 3 }))
+            )
+        );
+        assert_eq!(
+            parse_path_spec("src/scan.rs#matches_filters"),
+            (
+                PathBuf::from("src/scan.rs"),
+                Some(Fragment::Symbol("matches_filters".into()))
+            )
+        );
+        assert_eq!(
+            parse_path_spec("notes:todo.md"),
+            (PathBuf::from("notes:todo.md"), None)
+        );
+        assert_eq!(parse_path_spec("a.rs:5-2").1, None);
+        assert_eq!(parse_path_spec("issue#12.md").1, None);
+        assert_eq!(parse_path_spec("#main").1, None);
+        assert_eq!(
+            Fragment::parse(" 7 "),
+            Some(Fragment::Lines(LineRange { start: 7, end: 7 }))
+        );
+        assert_eq!(Fragment::parse("#run").unwrap().to_string(), "#run");
+
+        let text = "one\ntwo\nthree\nfour\n";
+        assert_eq!(LineRange { start: 2, end: 3 }.slice(text), "two\nthree\n");
+        assert_eq!(LineRange { start: 1, end: 1 }.slice(text), "one\n");
+        assert_eq!(LineRange { start: 3, end: 99 }.slice(text), "three\nfour\n");
+        assert_eq!(LineRange { start: 9, end: 10 }.slice(text), "");
+        let whole = LineRange {
+            start: 2,
+            end: usize::MAX,
+        };
+        assert_eq!(whole.slice(text), "two\nthree\nfour\n");
+    }
 }
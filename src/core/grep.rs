@@ -0,0 +1,133 @@
+use regex::Regex;
+
+use crate::core::content::{ContentMode, ContentType, Excerpt, FileContext};
+use crate::ports::tokenizer::TokenCounter;
+
+/// Applies `--grep`: returns whether the file's text matches `regex`.
+///
+/// With `context` (`--grep-context`), a matching file keeps only the lines of
+/// each match plus `context` lines around it, merged into excerpts when they
+/// touch; its body is then dropped.
+pub fn grep_file(
+    file: &mut FileContext,
+    regex: &Regex,
+    context: Option<usize>,
+    counter: &dyn TokenCounter,
+) -> bool {
+    let ContentType::Text(text) = &file.content else {
+        return false;
+    };
+    let Some(context) = context else {
+        return regex.is_match(text);
+    };
+
+    // Byte offset where each line starts; an empty text is one empty line.
+    let starts: Vec<usize> = std::iter::once(0)
+        .chain(
+            text.match_indices('\n')
+                .map(|(i, _)| i + 1)
+                .filter(|&start| start < text.len()),
+        )
+        .collect();
+    let line_of = |offset: usize| starts.partition_point(|&start| start <= offset).max(1) - 1;
+    let last_line = starts.len().saturating_sub(1);
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for found in regex.find_iter(text) {
+        let first = line_of(found.start()).saturating_sub(context);
+        let last = (line_of(found.end().max(found.start() + 1) - 1) + context).min(last_line);
+        match ranges.last_mut() {
+            Some(range) if first <= range.1 + 1 => range.1 = range.1.max(last),
+            _ => ranges.push((first, last)),
+        }
+    }
+    if ranges.is_empty() {
+        return false;
+    }
+
+    // Line numbers are those of the file, even when only a range was read.
+    let base = file.lines.map_or(1, |lines| lines.start);
+    file.excerpts = ranges
+        .into_iter()
+        .map(|(first, last)| {
+            let end = starts.get(last + 1).copied().unwrap_or(text.len());
+            Excerpt {
+                start: base + first,
+                end: base + last,
+                text: text[starts[first]..end].to_string(),
+                start_byte: file.start_byte + starts[first],
+            }
+        })
+        .collect();
+    file.token_count = file.excerpts.iter().map(|e| counter.count(&e.text)).sum();
+    file.content = ContentType::Text(String::new());
+    file.mode = ContentMode::Excerpts;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+
+        fn name(&self) -> &str {
+            "words"
+        }
+    }
+
+    fn file(text: &str) -> FileContext {
+        FileContext::new(
+            PathBuf::from("a.rs"),
+            PathBuf::from("a.rs"),
+            ContentType::Text(text.into()),
+            "rs".into(),
+            0,
+        )
+    }
+
+    #[test]
+    fn test_grep_excerpts() {
+        let text: String = (1..=12)
+            .map(|i| match i {
+                3 | 5 | 11 => format!("foo({})\n", i),
+                _ => format!("line {}\n", i),
+            })
+            .collect();
+        let regex = Regex::new(r"foo\(").unwrap();
+
+        let mut whole = file(&text);
+        assert!(grep_file(&mut whole, &regex, None, &WordCounter));
+        assert_eq!(whole.content, ContentType::Text(text.clone()));
+
+        let mut excerpts = file(&text);
+        assert!(grep_file(&mut excerpts, &regex, Some(1), &WordCounter));
+        assert_eq!(excerpts.mode, ContentMode::Excerpts);
+        let ranges: Vec<_> = excerpts.excerpts.iter().map(|e| (e.start, e.end)).collect();
+        assert_eq!(ranges, [(2, 6), (10, 12)]);
+        assert_eq!(excerpts.excerpts[1].text, "line 10\nfoo(11)\nline 12\n");
+
+        let mut empty = file("");
+        assert!(grep_file(
+            &mut empty,
+            &Regex::new("^").unwrap(),
+            Some(1),
+            &WordCounter
+        ));
+        assert_eq!((empty.excerpts[0].start, empty.excerpts[0].end), (1, 1));
+
+        let mut miss = file(&text);
+        assert!(!grep_file(
+            &mut miss,
+            &Regex::new("bar").unwrap(),
+            Some(1),
+            &WordCounter
+        ));
+    }
+}
@@ -0,0 +1,752 @@
+//! Small per-language lexers that split source text into code, comments and literals.
+//!
+//! They are not full tokenizers: they only track enough lexical state to know
+//! whether a byte belongs to a comment, to a string-like literal or to code,
+//! which is what comment stripping and minification need.
+
+use std::ops::Range;
+
+/// Language families sharing the same comment and literal syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LangFamily {
+    Rust,
+    /// C, C++ and Java.
+    CLike,
+    /// JavaScript and TypeScript.
+    JavaScript,
+    Go,
+    Python,
+    Shell,
+    Sql,
+}
+
+impl LangFamily {
+    /// Maps a language identifier (file extension) to its family.
+    pub fn from_language(language: &str) -> Option<Self> {
+        let family = match language.to_lowercase().as_str() {
+            "rs" | "rust" => Self::Rust,
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" | "java" => Self::CLike,
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => Self::JavaScript,
+            "go" => Self::Go,
+            "py" | "pyw" | "pyi" | "python" => Self::Python,
+            "sh" | "bash" | "zsh" | "ksh" => Self::Shell,
+            "sql" => Self::Sql,
+            _ => return None,
+        };
+        Some(family)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Code,
+    Comment,
+    /// Documentation comment (`///`, `/** */`, Go declaration comments, ...).
+    DocComment,
+    /// String-like literal whose bytes must be preserved (strings, raw strings, heredocs, ...).
+    Literal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub range: Range<usize>,
+}
+
+/// Splits `text` into consecutive segments covering every byte.
+pub fn lex(text: &str, family: LangFamily) -> Vec<Segment> {
+    let src = text.as_bytes();
+    let mut segments = match family {
+        LangFamily::Rust => lex_with(src, rust_token),
+        LangFamily::CLike => lex_with(src, c_like_token),
+        LangFamily::JavaScript => lex_with(src, javascript_token),
+        LangFamily::Go => lex_with(src, go_token),
+        LangFamily::Python => lex_with(src, python_token),
+        LangFamily::Shell => lex_with(src, ShellLexer::default().into_fn()),
+        LangFamily::Sql => lex_with(src, sql_token),
+    };
+    if family == LangFamily::Go {
+        mark_go_doc_comments(src, &mut segments);
+    }
+    segments
+}
+
+/// Drives a token recognizer over the source. The recognizer returns the kind and
+/// end of a token starting at `i`, or `None` if `i` is plain code.
+fn lex_with<F>(src: &[u8], mut next_token: F) -> Vec<Segment>
+where
+    F: FnMut(&[u8], usize) -> Option<(SegmentKind, usize)>,
+{
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut code_start = 0;
+    let mut i = 0;
+
+    while i < src.len() {
+        match next_token(src, i) {
+            Some((kind, end)) => {
+                let end = end.clamp(i + 1, src.len());
+                if kind == SegmentKind::Code {
+                    i = end;
+                    continue;
+                }
+                if code_start < i {
+                    segments.push(Segment {
+                        kind: SegmentKind::Code,
+                        range: code_start..i,
+                    });
+                }
+                segments.push(Segment {
+                    kind,
+                    range: i..end,
+                });
+                i = end;
+                code_start = end;
+            }
+            None => i += 1,
+        }
+    }
+    if code_start < src.len() {
+        segments.push(Segment {
+            kind: SegmentKind::Code,
+            range: code_start..src.len(),
+        });
+    }
+    segments
+}
+
+// --- Shared scanners -------------------------------------------------------
+
+fn is_ident(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+fn prev_is_ident(src: &[u8], i: usize) -> bool {
+    i > 0 && is_ident(src[i - 1])
+}
+
+/// End of a line comment: the position of the terminating newline (not included).
+fn line_end(src: &[u8], i: usize) -> usize {
+    src[i..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(src.len(), |p| i + p)
+}
+
+/// End (exclusive) of a `/* */` comment starting at `i`.
+fn block_comment_end(src: &[u8], i: usize, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut j = i;
+    while j + 1 < src.len() {
+        if src[j] == b'/' && src[j + 1] == b'*' && (nested || depth == 0) {
+            depth += 1;
+            j += 2;
+        } else if src[j] == b'*' && src[j + 1] == b'/' {
+            depth -= 1;
+            j += 2;
+            if depth == 0 {
+                return j;
+            }
+        } else {
+            j += 1;
+        }
+    }
+    src.len()
+}
+
+/// End (exclusive) of a literal opened by the `quote` byte at `open`.
+/// `escapes` enables backslash escapes, `doubled` treats a doubled quote as an escape.
+fn quoted_end(src: &[u8], open: usize, quote: u8, escapes: bool, doubled: bool) -> usize {
+    let mut j = open + 1;
+    while j < src.len() {
+        let b = src[j];
+        if escapes && b == b'\\' {
+            j += 2;
+        } else if b == quote {
+            if doubled && src.get(j + 1) == Some(&quote) {
+                j += 2;
+            } else {
+                return j + 1;
+            }
+        } else {
+            j += 1;
+        }
+    }
+    src.len()
+}
+
+/// End (exclusive) of a literal delimited by a multi-byte terminator, e.g. `"""`.
+fn delimited_end(src: &[u8], from: usize, terminator: &[u8], escapes: bool) -> usize {
+    let mut j = from;
+    while j < src.len() {
+        if escapes && src[j] == b'\\' {
+            j += 2;
+        } else if src[j..].starts_with(terminator) {
+            return j + terminator.len();
+        } else {
+            j += 1;
+        }
+    }
+    src.len()
+}
+
+/// Recognizes `//` and `/* */` comments with the given doc-comment markers.
+fn slash_comment(src: &[u8], i: usize, nested: bool) -> Option<(SegmentKind, usize)> {
+    if src[i] != b'/' {
+        return None;
+    }
+    let rest = &src[i..];
+    if rest.starts_with(b"//") {
+        let doc =
+            (rest.starts_with(b"///") && !rest.starts_with(b"////")) || rest.starts_with(b"//!");
+        return Some((comment_kind(doc), line_end(src, i)));
+    }
+    if rest.starts_with(b"/*") {
+        let doc =
+            (rest.starts_with(b"/**") && !rest.starts_with(b"/**/") && !rest.starts_with(b"/***"))
+                || rest.starts_with(b"/*!");
+        return Some((comment_kind(doc), block_comment_end(src, i, nested)));
+    }
+    None
+}
+
+fn comment_kind(doc: bool) -> SegmentKind {
+    if doc {
+        SegmentKind::DocComment
+    } else {
+        SegmentKind::Comment
+    }
+}
+
+// --- Rust ------------------------------------------------------------------
+
+fn rust_token(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    if let Some(token) = slash_comment(src, i, true) {
+        return Some(token);
+    }
+    let b = src[i];
+    if b == b'"' {
+        return Some((SegmentKind::Literal, quoted_end(src, i, b'"', true, false)));
+    }
+    if b == b'\'' {
+        return rust_char(src, i);
+    }
+    if matches!(b, b'r' | b'b' | b'c') && !prev_is_ident(src, i) {
+        return rust_prefixed_literal(src, i);
+    }
+    None
+}
+
+/// Char literal or lifetime. Lifetimes (`'a`) are plain code.
+fn rust_char(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    match src.get(i + 1) {
+        Some(b'\\') => Some((SegmentKind::Literal, quoted_end(src, i, b'\'', true, false))),
+        Some(_) => {
+            let ch_len = std::str::from_utf8(&src[i + 1..(i + 5).min(src.len())])
+                .or_else(|e| std::str::from_utf8(&src[i + 1..i + 1 + e.valid_up_to()]))
+                .ok()
+                .and_then(|s| s.chars().next())
+                .map_or(1, char::len_utf8);
+            if src.get(i + 1 + ch_len) == Some(&b'\'') {
+                Some((SegmentKind::Literal, i + 2 + ch_len))
+            } else {
+                None
+            }
+        }
+        None => None,
+    }
+}
+
+/// `b"..."`, `b'x'`, `r"..."`, `r#"..."#`, `br#"..."#`, `c"..."`, `cr#"..."#`.
+fn rust_prefixed_literal(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    let mut j = i;
+    if matches!(src[j], b'b' | b'c') {
+        j += 1;
+        match src.get(j) {
+            Some(b'"') => {
+                return Some((SegmentKind::Literal, quoted_end(src, j, b'"', true, false)))
+            }
+            Some(b'\'') if src[i] == b'b' => {
+                return Some((SegmentKind::Literal, quoted_end(src, j, b'\'', true, false)))
+            }
+            Some(b'r') => {}
+            _ => return None,
+        }
+    }
+    // Raw string: r, then hashes, then a quote.
+    j += 1;
+    let hashes = src[j..].iter().take_while(|&&b| b == b'#').count();
+    if src.get(j + hashes) != Some(&b'"') {
+        return None;
+    }
+    let mut terminator = vec![b'"'];
+    terminator.extend_from_slice("#".repeat(hashes).as_bytes());
+    Some((
+        SegmentKind::Literal,
+        delimited_end(src, j + hashes + 1, &terminator, false),
+    ))
+}
+
+// --- C / C++ / Java --------------------------------------------------------
+
+fn c_like_token(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    if let Some(token) = slash_comment(src, i, false) {
+        return Some(token);
+    }
+    match src[i] {
+        b'"' if src[i..].starts_with(b"\"\"\"") => {
+            // Java text block.
+            Some((
+                SegmentKind::Literal,
+                delimited_end(src, i + 3, b"\"\"\"", true),
+            ))
+        }
+        b'"' => Some((SegmentKind::Literal, quoted_end(src, i, b'"', true, false))),
+        b'\'' => Some((SegmentKind::Literal, quoted_end(src, i, b'\'', true, false))),
+        b'R' if src.get(i + 1) == Some(&b'"') => cpp_raw_string(src, i),
+        _ => None,
+    }
+}
+
+/// C++ raw string `R"delim( ... )delim"`.
+fn cpp_raw_string(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    let prefix_ok = !prev_is_ident(src, i)
+        || src[..i].ends_with(b"u8")
+        || matches!(src[i - 1], b'L' | b'u' | b'U');
+    if !prefix_ok {
+        return None;
+    }
+    let open = i + 2;
+    let paren = open + src[open..].iter().position(|&b| b == b'(')?;
+    let delim = &src[open..paren];
+    if delim.len() > 16 || delim.iter().any(|b| b.is_ascii_whitespace()) {
+        return None;
+    }
+    let mut terminator = vec![b')'];
+    terminator.extend_from_slice(delim);
+    terminator.push(b'"');
+    Some((
+        SegmentKind::Literal,
+        delimited_end(src, paren + 1, &terminator, false),
+    ))
+}
+
+// --- JavaScript / TypeScript -----------------------------------------------
+
+fn javascript_token(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    if let Some(token) = slash_comment(src, i, false) {
+        return Some(token);
+    }
+    match src[i] {
+        b'"' | b'\'' => Some((
+            SegmentKind::Literal,
+            quoted_end(src, i, src[i], true, false),
+        )),
+        b'`' => Some((SegmentKind::Literal, template_end(src, i))),
+        b'/' if regex_allowed(src, i) => Some((SegmentKind::Literal, regex_end(src, i))),
+        _ => None,
+    }
+}
+
+/// End (exclusive) of a template literal, following `${ ... }` substitutions.
+fn template_end(src: &[u8], open: usize) -> usize {
+    let mut j = open + 1;
+    while j < src.len() {
+        match src[j] {
+            b'\\' => j += 2,
+            b'`' => return j + 1,
+            b'$' if src.get(j + 1) == Some(&b'{') => j = substitution_end(src, j + 2),
+            _ => j += 1,
+        }
+    }
+    src.len()
+}
+
+/// End (exclusive) of a `${ ... }` substitution body starting at `from`.
+fn substitution_end(src: &[u8], from: usize) -> usize {
+    let mut depth = 1;
+    let mut j = from;
+    while j < src.len() {
+        match src[j] {
+            b'{' => {
+                depth += 1;
+                j += 1;
+            }
+            b'}' => {
+                depth -= 1;
+                j += 1;
+                if depth == 0 {
+                    return j;
+                }
+            }
+            b'"' | b'\'' => j = quoted_end(src, j, src[j], true, false),
+            b'`' => j = template_end(src, j),
+            _ => j += 1,
+        }
+    }
+    src.len()
+}
+
+/// A `/` starts a regex literal when it cannot be a division operator.
+fn regex_allowed(src: &[u8], i: usize) -> bool {
+    let before = &src[..i];
+    let Some(last) = before.iter().rposition(|b| !b.is_ascii_whitespace()) else {
+        return true;
+    };
+    let prev = before[last];
+    if b"(,=:[!&|?{};+-*%<>~^".contains(&prev) {
+        return true;
+    }
+    if is_ident(prev) {
+        let word_start = before[..=last]
+            .iter()
+            .rposition(|&b| !is_ident(b))
+            .map_or(0, |p| p + 1);
+        let word = &before[word_start..=last];
+        return matches!(
+            word,
+            b"return"
+                | b"typeof"
+                | b"instanceof"
+                | b"in"
+                | b"of"
+                | b"new"
+                | b"delete"
+                | b"void"
+                | b"throw"
+                | b"case"
+                | b"do"
+                | b"else"
+                | b"yield"
+                | b"await"
+        );
+    }
+    false
+}
+
+/// End (exclusive) of a regex literal, including its flags.
+fn regex_end(src: &[u8], open: usize) -> usize {
+    let mut j = open + 1;
+    let mut in_class = false;
+    while j < src.len() {
+        match src[j] {
+            b'\\' => j += 2,
+            b'\n' => return j,
+            b'[' => {
+                in_class = true;
+                j += 1;
+            }
+            b']' => {
+                in_class = false;
+                j += 1;
+            }
+            b'/' if !in_class => {
+                j += 1;
+                while j < src.len() && src[j].is_ascii_alphabetic() {
+                    j += 1;
+                }
+                return j;
+            }
+            _ => j += 1,
+        }
+    }
+    src.len()
+}
+
+// --- Go --------------------------------------------------------------------
+
+fn go_token(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    if src[i] == b'/' {
+        // Go has no doc-comment syntax; declaration comments are marked afterwards.
+        return slash_comment(src, i, false).map(|(_, end)| (SegmentKind::Comment, end));
+    }
+    match src[i] {
+        b'"' | b'\'' => Some((
+            SegmentKind::Literal,
+            quoted_end(src, i, src[i], true, false),
+        )),
+        b'`' => Some((SegmentKind::Literal, quoted_end(src, i, b'`', false, false))),
+        _ => None,
+    }
+}
+
+/// Go doc comments are the comment lines directly above a declaration.
+fn mark_go_doc_comments(src: &[u8], segments: &mut [Segment]) {
+    const DECLS: [&[u8]; 5] = [b"func", b"type", b"var", b"const", b"package"];
+
+    // Walk backwards so a comment can inherit the doc status of the comment below it.
+    for idx in (0..segments.len()).rev() {
+        if segments[idx].kind != SegmentKind::Comment {
+            continue;
+        }
+        let end = segments[idx].range.end;
+        let rest = &src[end..];
+        let Some(nl) = rest.iter().position(|&b| b == b'\n') else {
+            continue;
+        };
+        if !rest[..nl].iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let line_start = end + nl + 1;
+        let j = line_start
+            + src[line_start..]
+                .iter()
+                .take_while(|b| **b == b' ' || **b == b'\t')
+                .count();
+
+        let next_is_doc = segments[idx + 1..]
+            .iter()
+            .find(|s| s.range.start == j)
+            .is_some_and(|s| s.kind == SegmentKind::DocComment);
+        let starts_decl = DECLS.iter().any(|decl| {
+            src[j..].starts_with(decl) && src.get(j + decl.len()).is_some_and(|b| !is_ident(*b))
+        });
+        if next_is_doc || starts_decl {
+            segments[idx].kind = SegmentKind::DocComment;
+        }
+    }
+}
+
+// --- Python ----------------------------------------------------------------
+
+fn python_token(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    let b = src[i];
+    if b == b'#' {
+        return Some((SegmentKind::Comment, line_end(src, i)));
+    }
+    // Optional string prefix: r, b, u, f and their two-letter combinations.
+    let mut q = i;
+    if b.is_ascii_alphabetic() {
+        if prev_is_ident(src, i) {
+            return None;
+        }
+        let prefix = src[i..]
+            .iter()
+            .take_while(|b| b"rRbBuUfF".contains(b))
+            .count();
+        if prefix == 0 || prefix > 2 {
+            return None;
+        }
+        q = i + prefix;
+    }
+    match src.get(q) {
+        Some(&quote @ (b'"' | b'\'')) => {
+            let triple = [quote; 3];
+            if src[q..].starts_with(&triple) {
+                Some((
+                    SegmentKind::Literal,
+                    delimited_end(src, q + 3, &triple, true),
+                ))
+            } else {
+                Some((SegmentKind::Literal, quoted_end(src, q, quote, true, false)))
+            }
+        }
+        _ => None,
+    }
+}
+
+// --- Shell -----------------------------------------------------------------
+
+/// Shell lexing needs state: heredoc bodies start on the line after their `<<WORD`,
+/// and inside `$((...))` a `<<` is a shift, not a heredoc.
+#[derive(Default)]
+struct ShellLexer {
+    /// Pending heredocs: (terminator word, strip leading tabs).
+    pending: Vec<(Vec<u8>, bool)>,
+    /// Open parentheses of the arithmetic expansion being lexed, if any.
+    arithmetic: usize,
+}
+
+impl ShellLexer {
+    fn into_fn(mut self) -> impl FnMut(&[u8], usize) -> Option<(SegmentKind, usize)> {
+        move |src, i| self.token(src, i)
+    }
+
+    fn token(&mut self, src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+        let b = src[i];
+        match b {
+            b'\n' if !self.pending.is_empty() => {
+                Some((SegmentKind::Literal, self.heredoc_end(src, i + 1)))
+            }
+            b'#' if i == 0 && src.get(1) == Some(&b'!') => {
+                Some((SegmentKind::Code, line_end(src, i)))
+            }
+            b'#' if i == 0
+                || src[i - 1].is_ascii_whitespace()
+                || b";&|()".contains(&src[i - 1]) =>
+            {
+                Some((SegmentKind::Comment, line_end(src, i)))
+            }
+            b'\\' => Some((SegmentKind::Code, i + 2)),
+            b'\'' => Some((
+                SegmentKind::Literal,
+                quoted_end(src, i, b'\'', false, false),
+            )),
+            b'"' => Some((SegmentKind::Literal, quoted_end(src, i, b'"', true, false))),
+            b'$' if src.get(i + 1) == Some(&b'\'') => Some((
+                SegmentKind::Literal,
+                quoted_end(src, i + 1, b'\'', true, false),
+            )),
+            b'$' if self.arithmetic == 0 && src[i..].starts_with(b"$((") => {
+                self.arithmetic = 2;
+                Some((SegmentKind::Code, i + 3))
+            }
+            b'(' if self.arithmetic > 0 => {
+                self.arithmetic += 1;
+                Some((SegmentKind::Code, i + 1))
+            }
+            b')' if self.arithmetic > 0 => {
+                self.arithmetic -= 1;
+                Some((SegmentKind::Code, i + 1))
+            }
+            b'<' if self.arithmetic == 0
+                && src[i..].starts_with(b"<<")
+                && !src[i..].starts_with(b"<<<") =>
+            {
+                self.heredoc_operator(src, i)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses `<<WORD`, `<<-WORD`, `<<'WORD'` and `<<"WORD"`; the operator itself is code.
+    fn heredoc_operator(&mut self, src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+        let mut j = i + 2;
+        let strip_tabs = src.get(j) == Some(&b'-');
+        if strip_tabs {
+            j += 1;
+        }
+        while src.get(j).is_some_and(|b| *b == b' ' || *b == b'\t') {
+            j += 1;
+        }
+        let quote = src.get(j).copied().filter(|b| *b == b'\'' || *b == b'"');
+        if quote.is_some() {
+            j += 1;
+        }
+        let start = j;
+        while src
+            .get(j)
+            .is_some_and(|b| is_ident(*b) || *b == b'-' || *b == b'.')
+        {
+            j += 1;
+        }
+        if j == start {
+            return None;
+        }
+        self.pending.push((src[start..j].to_vec(), strip_tabs));
+        if quote.is_some() && src.get(j) == quote.as_ref() {
+            j += 1;
+        }
+        Some((SegmentKind::Code, j))
+    }
+
+    /// Consumes all pending heredoc bodies starting at `from`, returning the end of the last terminator line.
+    fn heredoc_end(&mut self, src: &[u8], from: usize) -> usize {
+        let mut j = from;
+        for (word, strip_tabs) in std::mem::take(&mut self.pending) {
+            while j < src.len() {
+                let end = line_end(src, j);
+                let mut line = &src[j..end];
+                if strip_tabs {
+                    let tabs = line.iter().take_while(|b| **b == b'\t').count();
+                    line = &line[tabs..];
+                }
+                j = end;
+                if line == word.as_slice() {
+                    break;
+                }
+                j += 1;
+            }
+        }
+        j
+    }
+}
+
+// --- SQL -------------------------------------------------------------------
+
+fn sql_token(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    match src[i] {
+        b'-' if src.get(i + 1) == Some(&b'-') => Some((SegmentKind::Comment, line_end(src, i))),
+        b'/' if src.get(i + 1) == Some(&b'*') => {
+            Some((SegmentKind::Comment, block_comment_end(src, i, false)))
+        }
+        b'\'' | b'"' => Some((
+            SegmentKind::Literal,
+            quoted_end(src, i, src[i], false, true),
+        )),
+        b'$' => dollar_quoted(src, i),
+        _ => None,
+    }
+}
+
+/// PostgreSQL dollar-quoted string: `$$...$$` or `$tag$...$tag$`.
+fn dollar_quoted(src: &[u8], i: usize) -> Option<(SegmentKind, usize)> {
+    if prev_is_ident(src, i) {
+        return None;
+    }
+    let tag_len = src[i + 1..]
+        .iter()
+        .take_while(|b| is_ident(**b) && !b.is_ascii_digit())
+        .count();
+    if src.get(i + 1 + tag_len) != Some(&b'$') {
+        return None;
+    }
+    let tag = &src[i..i + tag_len + 2];
+    Some((
+        SegmentKind::Literal,
+        delimited_end(src, i + tag.len(), tag, false),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str, family: LangFamily) -> Vec<(SegmentKind, &str)> {
+        lex(text, family)
+            .into_iter()
+            .map(|s| (s.kind, &text[s.range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_literals_and_lifetimes() {
+        let src = "fn f<'a>(x: &'a str) -> char { let _ = r#\"// no\"#; '/' }";
+        let segs = kinds(src, LangFamily::Rust);
+
+        assert!(segs.contains(&(SegmentKind::Literal, "r#\"// no\"#")));
+        assert!(segs.contains(&(SegmentKind::Literal, "'/'")));
+        assert!(segs.iter().all(|(k, _)| *k != SegmentKind::Comment));
+    }
+
+    #[test]
+    fn test_go_doc_comments() {
+        let src = "// Package x.\npackage x\n\n// helper\n\nx := 1 // trailing\n";
+        let segs = kinds(src, LangFamily::Go);
+
+        assert!(segs.contains(&(SegmentKind::DocComment, "// Package x.")));
+        assert!(segs.contains(&(SegmentKind::Comment, "// helper")));
+        assert!(segs.contains(&(SegmentKind::Comment, "// trailing")));
+    }
+
+    #[test]
+    fn test_shell_heredoc_is_literal() {
+        let src = "cat <<EOF\n# not a comment\nEOF\necho $# # real\n";
+        let segs = kinds(src, LangFamily::Shell);
+
+        assert!(segs.contains(&(SegmentKind::Literal, "\n# not a comment\nEOF")));
+        assert!(segs.contains(&(SegmentKind::Comment, "# real")));
+    }
+
+    #[test]
+    fn test_shell_arithmetic_shift_is_not_heredoc() {
+        let src = "x=$(( (1<<2) + 1 ))
+# gone
+echo $x # also gone
+";
+        let segs = kinds(src, LangFamily::Shell);
+
+        assert!(segs.contains(&(SegmentKind::Comment, "# gone")));
+        assert!(segs.contains(&(SegmentKind::Comment, "# also gone")));
+        assert!(segs.iter().all(|(k, _)| *k != SegmentKind::Literal));
+    }
+}
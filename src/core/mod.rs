@@ -1,5 +1,20 @@
 //! Core module containing Domain Entities and Business Logic.
 
+pub mod budget;
+pub mod chunk;
+pub mod collapse;
 pub mod config;
 pub mod content;
+pub mod dedup;
+pub mod fidelity;
 pub mod file;
+pub mod grep;
+pub mod lexer;
+pub mod noise;
+pub mod patch;
+pub mod pattern;
+pub mod related;
+pub mod report;
+pub mod skeleton;
+pub mod split;
+pub mod truncate;
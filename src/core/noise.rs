@@ -0,0 +1,175 @@
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+/// Built-in noise names of one ecosystem.
+struct Ecosystem {
+    /// Lock files and OS/editor artefacts, matched by exact file name.
+    files: &'static [&'static str],
+    /// Dependency, cache and build output directories, matched by exact name.
+    dirs: &'static [&'static str],
+}
+
+/// Version control internals, dropped whatever the settings say.
+const VCS_DIRS: &[&str] = &[".git", ".svn", ".hg"];
+
+const ECOSYSTEMS: &[Ecosystem] = &[
+    // Operating systems and editors
+    Ecosystem {
+        files: &[".DS_Store", "Thumbs.db"],
+        dirs: &[".idea", ".vscode"],
+    },
+    // Rust
+    Ecosystem {
+        files: &["Cargo.lock"],
+        dirs: &["target"],
+    },
+    // JavaScript / TypeScript
+    Ecosystem {
+        files: &[
+            "package-lock.json",
+            "yarn.lock",
+            "pnpm-lock.yaml",
+            "bun.lockb",
+        ],
+        dirs: &[
+            "node_modules",
+            "dist",
+            ".next",
+            ".nuxt",
+            ".turbo",
+            "coverage",
+        ],
+    },
+    // Python
+    Ecosystem {
+        files: &["poetry.lock", "Pipfile.lock", "uv.lock"],
+        dirs: &[
+            "__pycache__",
+            ".venv",
+            "venv",
+            ".pytest_cache",
+            ".mypy_cache",
+            ".tox",
+        ],
+    },
+    // JVM (Gradle, Maven)
+    Ecosystem {
+        files: &[],
+        dirs: &["build", ".gradle"],
+    },
+    // Go, PHP, Ruby
+    Ecosystem {
+        files: &["go.sum", "composer.lock", "Gemfile.lock"],
+        dirs: &["vendor"],
+    },
+    // Terraform
+    Ecosystem {
+        files: &[".terraform.lock.hcl"],
+        dirs: &[".terraform"],
+    },
+];
+
+/// `[noise]` section of the project config file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NoiseSettings {
+    /// Start from the built-in lists (`false` replaces them entirely).
+    pub defaults: bool,
+    /// Extra file names to drop.
+    pub files: Vec<String>,
+    /// Extra directory names to drop.
+    pub dirs: Vec<String>,
+    /// Names removed from the lists, e.g. a real `build/` source directory.
+    pub keep: Vec<String>,
+}
+
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        Self {
+            defaults: true,
+            files: Vec::new(),
+            dirs: Vec::new(),
+            keep: Vec::new(),
+        }
+    }
+}
+
+/// File and directory names dropped during the scan, before any ignore rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoiseRules {
+    pub files: BTreeSet<String>,
+    pub dirs: BTreeSet<String>,
+}
+
+impl Default for NoiseRules {
+    /// The built-in lists of every ecosystem.
+    fn default() -> Self {
+        Self::resolve(&NoiseSettings::default(), true)
+    }
+}
+
+impl NoiseRules {
+    /// Builds the effective lists from the config file settings. `use_defaults`
+    /// is `false` for `--no-default-noise`.
+    pub fn resolve(settings: &NoiseSettings, use_defaults: bool) -> Self {
+        let mut rules = Self {
+            files: BTreeSet::new(),
+            dirs: BTreeSet::new(),
+        };
+        if use_defaults && settings.defaults {
+            for ecosystem in ECOSYSTEMS {
+                rules
+                    .files
+                    .extend(ecosystem.files.iter().map(|s| s.to_string()));
+                rules
+                    .dirs
+                    .extend(ecosystem.dirs.iter().map(|s| s.to_string()));
+            }
+        }
+        rules.files.extend(settings.files.iter().cloned());
+        rules.dirs.extend(settings.dirs.iter().cloned());
+        for name in &settings.keep {
+            rules.files.remove(name);
+            rules.dirs.remove(name);
+        }
+        rules.dirs.extend(VCS_DIRS.iter().map(|s| s.to_string()));
+        rules
+    }
+
+    pub fn is_noise(&self, name: &str, is_dir: bool) -> bool {
+        self.files.contains(name) || (is_dir && self.dirs.contains(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_settings_extend_and_override() {
+        let defaults = NoiseRules::default();
+        assert!(defaults.is_noise("node_modules", true));
+        assert!(defaults.is_noise("Cargo.lock", false));
+        assert!(!defaults.is_noise("target", false), "Only directories");
+
+        let settings: NoiseSettings = toml::from_str(
+            r#"
+            dirs = ["generated"]
+            keep = ["build"]
+            "#,
+        )
+        .unwrap();
+        let rules = NoiseRules::resolve(&settings, true);
+        assert!(rules.is_noise("generated", true));
+        assert!(!rules.is_noise("build", true));
+        assert!(rules.is_noise(".gradle", true));
+
+        let replaced = NoiseRules::resolve(&settings, false);
+        assert_eq!(replaced.dirs.len(), 1 + VCS_DIRS.len());
+        assert!(replaced.files.is_empty());
+        assert!(
+            replaced.is_noise(".git", true),
+            "VCS internals always stay out"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::core::config::DiffMode;
+use crate::core::content::{ContentMode, ContentType, FileContext};
+use crate::ports::tokenizer::TokenCounter;
+
+/// Attaches to each file its patch from `--diff` (keyed by relative path).
+///
+/// Unless `mode` keeps the full file, text bodies are dropped and the file is
+/// represented by its diff alone. Files without a patch, such as related files
+/// pulled in by `--with-related`, are left untouched.
+pub fn attach_diffs(
+    files: &mut [FileContext],
+    diffs: &HashMap<PathBuf, String>,
+    mode: DiffMode,
+    counter: &dyn TokenCounter,
+) {
+    for file in files.iter_mut() {
+        let Some(diff) = diffs.get(&file.relative_path) else {
+            continue;
+        };
+        if mode != DiffMode::Full && matches!(file.content, ContentType::Text(_)) {
+            file.content = ContentType::Text(String::new());
+            file.token_count = 0;
+            file.mode = ContentMode::Diff;
+        }
+        file.token_count += counter.count(diff);
+        file.diff = Some(diff.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct LineCounter;
+
+    impl TokenCounter for LineCounter {
+        fn count(&self, text: &str) -> usize {
+            text.lines().count()
+        }
+
+        fn name(&self) -> &str {
+            "lines"
+        }
+    }
+
+    #[test]
+    fn test_attach_diffs_modes() {
+        let file = |path: &str| {
+            FileContext::new(
+                PathBuf::from(path),
+                PathBuf::from(path),
+                ContentType::Text("a\nb\nc\n".into()),
+                "rs".into(),
+                3,
+            )
+        };
+        let diffs = HashMap::from([(PathBuf::from("a.rs"), "-a\n+A\n".to_string())]);
+
+        let mut full = vec![file("a.rs"), file("b.rs")];
+        attach_diffs(&mut full, &diffs, DiffMode::Full, &LineCounter);
+        assert_eq!((full[0].token_count, full[0].mode), (5, ContentMode::Full));
+        assert_eq!(full[1].diff, None);
+
+        let mut only = vec![file("a.rs")];
+        attach_diffs(&mut only, &diffs, DiffMode::Only, &LineCounter);
+        assert_eq!((only[0].token_count, only[0].mode), (2, ContentMode::Diff));
+        assert_eq!(only[0].content, ContentType::Text(String::new()));
+        assert_eq!(only[0].diff.as_deref(), Some("-a\n+A\n"));
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::Path;
+
+/// Matches project-relative paths against gitignore-style glob patterns.
+///
+/// Patterns follow `.gitignore` rules: `*.rs` matches at any depth, `src/**/*.rs`
+/// is anchored to the project root, a pattern matching a directory matches
+/// everything below it and a leading `!` negates an earlier match.
+#[derive(Debug, Clone)]
+pub struct PathMatcher {
+    globs: Gitignore,
+}
+
+impl PathMatcher {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut builder = GitignoreBuilder::new("");
+        for pattern in patterns {
+            builder
+                .add_line(None, pattern)
+                .with_context(|| format!("Invalid glob pattern: {}", pattern))?;
+        }
+        Ok(Self {
+            globs: builder.build()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.globs.is_empty()
+    }
+
+    /// True when the last pattern matching the path (or one of its parent
+    /// directories) is not a negation.
+    pub fn is_match(&self, relative_path: &Path) -> bool {
+        self.globs
+            .matched_path_or_any_parents(relative_path, false)
+            .is_ignore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(patterns: &[&str]) -> PathMatcher {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        PathMatcher::new(&patterns).unwrap()
+    }
+
+    #[test]
+    fn test_gitignore_style_matching() {
+        let m = matcher(&["*.rs", "!src/generated/**", "docs/"]);
+
+        assert!(m.is_match(Path::new("main.rs")));
+        assert!(m.is_match(Path::new("src/core/mod.rs")));
+        assert!(!m.is_match(Path::new("src/generated/api.rs")));
+        assert!(m.is_match(Path::new("docs/guide/intro.md")));
+        assert!(!m.is_match(Path::new("README.md")));
+
+        let anchored = matcher(&["src/core/*.rs"]);
+        assert!(anchored.is_match(Path::new("src/core/config.rs")));
+        assert!(!anchored.is_match(Path::new("lib/src/core/config.rs")));
+    }
+}
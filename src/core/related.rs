@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+/// File stems that name their directory rather than themselves.
+const INDEX_STEMS: &[&str] = &["index", "__init__"];
+/// Crate roots and module files: their directory name shows up in nearly every
+/// Rust `use` path, so it says nothing about who depends on them.
+const ROOT_STEMS: &[&str] = &["mod", "lib", "main"];
+
+/// Keywords that start an import-like line in the supported languages.
+const IMPORT_KEYWORDS: &[&str] = &[
+    "use ", "pub use ", "mod ", "pub mod ", "import ", "from ", "export ", "#include",
+];
+
+/// Name other files use to import `path`: its stem, or its directory name for
+/// index files such as `mod.rs`, `index.ts` or `__init__.py`.
+pub fn module_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    // `index.d.ts` and friends: the stem proper ends at the first dot.
+    let stem = stem.split('.').next().unwrap_or(stem);
+    if ROOT_STEMS.contains(&stem) {
+        return None;
+    }
+    if INDEX_STEMS.contains(&stem) {
+        return path.parent()?.file_name()?.to_str().map(str::to_string);
+    }
+    Some(stem.to_string())
+}
+
+/// Whether an import line of `text` mentions one of `names` as a whole word.
+pub fn imports_any(text: &str, names: &HashSet<String>) -> bool {
+    text.lines().map(str::trim_start).any(|line| {
+        let is_import = IMPORT_KEYWORDS.iter().any(|k| line.starts_with(k))
+            || line.contains("require(")
+            || line.contains("import(");
+        is_import
+            && line
+                .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                .any(|word| names.contains(word))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_importers_of_module() {
+        assert_eq!(
+            module_name(Path::new("src/core/budget.rs")).as_deref(),
+            Some("budget")
+        );
+        assert_eq!(
+            module_name(Path::new("web/api/index.ts")).as_deref(),
+            Some("api")
+        );
+
+        let names: HashSet<String> = ["budget", "api"].map(String::from).into();
+        assert!(imports_any(
+            "use crate::core::budget::apply_budget;\n",
+            &names
+        ));
+        assert!(imports_any("import { get } from '../api';\n", &names));
+        assert!(imports_any("const api = require(\"./api\");\n", &names));
+        assert!(!imports_any(
+            "// the budget is tight\nlet budget = 3;\n",
+            &names
+        ));
+        assert!(!imports_any("use crate::core::budgets;\n", &names));
+    }
+}
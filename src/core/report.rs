@@ -0,0 +1,79 @@
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::core::content::FileContext;
+
+/// Reason why a scanned file is not part of the report body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OmissionReason {
+    /// Including the file would exceed `--max-tokens`.
+    TokenBudget,
+    /// The file it duplicates or is collapsed into was left out.
+    ReferenceOmitted,
+}
+
+impl OmissionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TokenBudget => "token_budget",
+            Self::ReferenceOmitted => "reference_omitted",
+        }
+    }
+}
+
+/// A file that was scanned and read but left out of the report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OmittedFile {
+    pub relative_path: PathBuf,
+    pub token_count: usize,
+    pub reason: OmissionReason,
+}
+
+/// Position of one part in a report split by `--split-tokens`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PartInfo {
+    pub index: usize,
+    pub total: usize,
+}
+
+/// Files replaced by a reference to an identical earlier file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DuplicateStats {
+    pub files: usize,
+    pub tokens_saved: usize,
+}
+
+/// Near-duplicate files collapsed into a diff against their cluster representative.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SimilarStats {
+    pub clusters: usize,
+    pub files: usize,
+    pub tokens_saved: usize,
+}
+
+/// Report-level metadata produced by the processing phases and rendered by every writer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReportMeta {
+    pub omitted: Vec<OmittedFile>,
+    pub part: Option<PartInfo>,
+    /// Full file list for the directory tree when the report only holds a subset
+    /// of the files (e.g. one part of a split report).
+    pub tree: Option<Vec<PathBuf>>,
+    pub duplicates: DuplicateStats,
+    pub similar: SimilarStats,
+}
+
+impl ReportMeta {
+    /// Paths that belong in the directory tree: emitted files plus elided ones.
+    pub fn tree_paths<'a>(&'a self, files: &'a [FileContext]) -> Vec<&'a PathBuf> {
+        let emitted: Vec<&PathBuf> = match &self.tree {
+            Some(tree) => tree.iter().collect(),
+            None => files.iter().map(|f| &f.relative_path).collect(),
+        };
+        emitted
+            .into_iter()
+            .chain(self.omitted.iter().map(|o| &o.relative_path))
+            .collect()
+    }
+}
@@ -0,0 +1,535 @@
+use crate::core::content::{strip_comments, ContentMode, ContentType, FileContext};
+use crate::core::file::LineRange;
+use crate::core::lexer::{lex, LangFamily, SegmentKind};
+use crate::ports::tokenizer::TokenCounter;
+
+/// What to do with a `{ ... }` block found at item level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    /// `impl`, `trait`, `mod`, `class`, ...: keep the header and walk the items inside.
+    Container,
+    /// `struct`, `enum`, `interface`, imports, ...: keep verbatim.
+    Definition,
+    /// Function bodies and anything else: elide as `{ ... }`.
+    Body,
+}
+
+/// Replaces the body of a source file with its skeleton and recounts its tokens.
+///
+/// Returns `true` when the file was reduced. Non-text content and languages
+/// without skeleton support are left untouched.
+pub fn apply_skeleton(file: &mut FileContext, counter: &dyn TokenCounter) -> bool {
+    let ContentType::Text(text) = &file.content else {
+        return false;
+    };
+    let Some(skeleton) = skeletonize(text, &file.language) else {
+        return false;
+    };
+
+    file.token_count = counter.count(&skeleton);
+    file.content = ContentType::Text(skeleton);
+    file.mode = ContentMode::Skeleton;
+    true
+}
+
+/// Reduces source text to signatures, type definitions and doc comments.
+///
+/// Function bodies become `{ ... }` (`...` in Python) and regular comments are
+/// dropped. Returns `None` for languages without skeleton support.
+pub fn skeletonize(text: &str, language: &str) -> Option<String> {
+    let family = LangFamily::from_language(language)?;
+    let text = strip_comments(text, language, true);
+    match family {
+        LangFamily::Rust | LangFamily::CLike | LangFamily::JavaScript | LangFamily::Go => {
+            Some(brace_skeleton(&text, family))
+        }
+        LangFamily::Python => Some(python_skeleton(&text)),
+        LangFamily::Shell | LangFamily::Sql => None,
+    }
+}
+
+/// Copy of `text` where every byte outside code segments is a space, so that
+/// braces and keywords inside literals and comments are never seen.
+fn code_only(text: &str, family: LangFamily) -> String {
+    let mut masked = String::with_capacity(text.len());
+    for segment in lex(text, family) {
+        if segment.kind == SegmentKind::Code {
+            masked.push_str(&text[segment.range]);
+        } else {
+            masked.push_str(&" ".repeat(segment.range.len()));
+        }
+    }
+    masked
+}
+
+fn brace_skeleton(text: &str, family: LangFamily) -> String {
+    let masked = code_only(text, family);
+    let bytes = masked.as_bytes();
+
+    let mut skeleton = String::with_capacity(text.len() / 2);
+    let mut cursor = 0;
+    let mut header_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b';' | b'}' => header_start = i + 1,
+            b'{' => match classify(&masked[header_start..i]) {
+                BlockKind::Container => header_start = i + 1,
+                kind => {
+                    let close = matching_brace_end(bytes, i);
+                    if kind == BlockKind::Body {
+                        skeleton.push_str(&text[cursor..i]);
+                        skeleton.push_str("{ ... }");
+                        cursor = close;
+                    }
+                    header_start = close;
+                    i = close;
+                    continue;
+                }
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+    skeleton.push_str(&text[cursor..]);
+    skeleton
+}
+
+/// Classifies a block by the code between the previous item and its `{`.
+fn classify(header: &str) -> BlockKind {
+    let words: Vec<&str> = header
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    if header.contains("=>")
+        || words
+            .iter()
+            .any(|w| matches!(*w, "fn" | "func" | "function"))
+    {
+        return BlockKind::Body;
+    }
+    // `import { a } from "b"`, `export { a }`, `import type { A }`.
+    if matches!(words.last(), Some(&("import" | "export" | "type"))) {
+        return BlockKind::Definition;
+    }
+    for word in words {
+        match word {
+            "struct" | "enum" | "union" | "interface" | "type" | "use" | "import" => {
+                return BlockKind::Definition
+            }
+            "impl" | "trait" | "mod" | "class" | "record" | "namespace" | "module" | "extern" => {
+                return BlockKind::Container
+            }
+            _ => {}
+        }
+    }
+    BlockKind::Body
+}
+
+/// Position just past the `}` closing the brace at `open` (or the end of the text).
+fn matching_brace_end(bytes: &[u8], open: usize) -> usize {
+    let mut depth = 0;
+    for (i, &b) in bytes.iter().enumerate().skip(open) {
+        match b {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    bytes.len()
+}
+
+/// A Python logical line: one statement, possibly spread over several physical lines.
+struct LogicalLine<'a> {
+    text: &'a str,
+    /// The statement without its literals, trimmed.
+    code: &'a str,
+    indent: usize,
+}
+
+impl LogicalLine<'_> {
+    fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    /// Compound statement header (`def f():`, `if x:`, ...).
+    fn opens_block(&self) -> bool {
+        self.code.ends_with(':')
+    }
+
+    fn starts_with_keyword(&self, keyword: &str) -> bool {
+        let code = self.code.strip_prefix("async ").unwrap_or(self.code);
+        code.strip_prefix(keyword)
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '('))
+    }
+
+    /// A statement made of a single string literal.
+    fn is_docstring(&self) -> bool {
+        !self.is_blank() && self.code.is_empty()
+    }
+}
+
+fn logical_lines<'a>(text: &'a str, masked: &'a str) -> Vec<LogicalLine<'a>> {
+    let bytes = masked.as_bytes();
+    let mut lines = Vec::new();
+    let mut push = |start: usize, end: usize| {
+        let line = &text[start..end];
+        lines.push(LogicalLine {
+            text: line,
+            code: masked[start..end].trim(),
+            indent: line.len() - line.trim_start().len(),
+        });
+    };
+
+    let mut start = 0;
+    let mut depth = 0usize;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            b'\n' if depth == 0 && (i == 0 || bytes[i - 1] != b'\\') => {
+                push(start, i + 1);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < text.len() {
+        push(start, text.len());
+    }
+    lines
+}
+
+/// Keeps imports, class-level statements, decorators, `class`/`def` headers and
+/// docstrings; function bodies and other compound statements are dropped.
+fn python_skeleton(text: &str) -> String {
+    let masked = code_only(text, LangFamily::Python);
+    let lines = logical_lines(text, &masked);
+
+    let mut skeleton = String::with_capacity(text.len() / 2);
+    let mut skip_deeper_than: Option<usize> = None;
+    let mut blank_pending = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_blank() {
+            blank_pending = true;
+            continue;
+        }
+        if let Some(level) = skip_deeper_than {
+            if line.indent > level {
+                continue;
+            }
+            skip_deeper_than = None;
+        }
+        let is_def = line.starts_with_keyword("def") && line.opens_block();
+        let is_header = line.code.starts_with('@') || line.starts_with_keyword("class");
+        if !is_def && !is_header && line.opens_block() {
+            // Other compound statements (`if`, `for`, `try`, ...) are dropped whole.
+            skip_deeper_than = Some(line.indent);
+            continue;
+        }
+        if std::mem::take(&mut blank_pending) && !skeleton.is_empty() {
+            skeleton.push('\n');
+        }
+
+        if is_def {
+            push_line(&mut skeleton, line.text);
+            if let Some(body) = lines[i + 1..].iter().find(|l| !l.is_blank()) {
+                if body.indent > line.indent {
+                    if body.is_docstring() {
+                        push_line(&mut skeleton, body.text);
+                    }
+                    skeleton.push_str(&body.text[..body.indent]);
+                    skeleton.push_str("...\n");
+                }
+            }
+            skip_deeper_than = Some(line.indent);
+        } else {
+            push_line(&mut skeleton, line.text);
+        }
+    }
+    skeleton
+}
+
+fn push_line(buffer: &mut String, line: &str) {
+    buffer.push_str(line);
+    if !line.ends_with('\n') {
+        buffer.push('\n');
+    }
+}
+
+/// Keywords after which a block header names the item it opens.
+const DECLARING: &[&str] = &[
+    "fn",
+    "func",
+    "function",
+    "def",
+    "struct",
+    "enum",
+    "union",
+    "trait",
+    "interface",
+    "class",
+    "type",
+    "mod",
+    "impl",
+    "record",
+    "namespace",
+    "module",
+    "const",
+    "let",
+    "var",
+];
+
+/// Statements whose `word(` header is not a function definition.
+const CONTROL: &[&str] = &[
+    "if", "else", "for", "while", "switch", "match", "catch", "do", "return", "loop",
+];
+
+/// Lines of the first item named `name` (function, method, type, class...),
+/// including the doc comments and attributes above it (`path#symbol`).
+///
+/// Returns `None` for languages without skeleton support, or when no item
+/// with a body has that name.
+pub fn symbol_lines(text: &str, language: &str, name: &str) -> Option<LineRange> {
+    let (start, end) = match LangFamily::from_language(language)? {
+        family @ (LangFamily::Rust
+        | LangFamily::CLike
+        | LangFamily::JavaScript
+        | LangFamily::Go) => brace_symbol(text, family, name)?,
+        LangFamily::Python => python_symbol(text, name)?,
+        LangFamily::Shell | LangFamily::Sql => return None,
+    };
+    let line_at = |offset: usize| text[..offset].matches('\n').count() + 1;
+    Some(LineRange {
+        start: line_at(start),
+        end: line_at(end - 1),
+    })
+}
+
+/// Byte range of the item named `name`, walking items the way `brace_skeleton` does.
+fn brace_symbol(text: &str, family: LangFamily, name: &str) -> Option<(usize, usize)> {
+    let masked = code_only(text, family);
+    let bytes = masked.as_bytes();
+
+    let mut header_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b';' | b'}' => header_start = i + 1,
+            b'{' => {
+                let header = &masked[header_start..i];
+                if declares(header, name) {
+                    // Comments are blank in `masked`, so the header also holds
+                    // the doc lines above the item, and whatever precedes the
+                    // last blank line (`package main` in Go).
+                    let item = &text[header_start..i];
+                    let mut start = header_start + item.len() - item.trim_start().len();
+                    let mut offset = header_start;
+                    for line in item.split_inclusive('\n') {
+                        offset += line.len();
+                        if line.trim().is_empty() {
+                            start = start.max(offset);
+                        }
+                    }
+                    return Some((start, matching_brace_end(bytes, i)));
+                }
+                if classify(header) == BlockKind::Container {
+                    header_start = i + 1;
+                } else {
+                    let close = matching_brace_end(bytes, i);
+                    header_start = close;
+                    i = close;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Whether a block header names `name`: right after a declaring keyword
+/// (`fn name`, `class name`, `const name = () =>`) or as a method (`name(...)`).
+fn declares(header: &str, name: &str) -> bool {
+    let words: Vec<&str> = header
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.first().is_some_and(|w| CONTROL.contains(w)) {
+        return false;
+    }
+    words.iter().enumerate().any(|(k, &word)| {
+        if word != name {
+            return false;
+        }
+        // `word` borrows `header`, so its offset is the pointer difference.
+        let rest = &header[word.as_ptr() as usize - header.as_ptr() as usize + word.len()..];
+        (k > 0 && DECLARING.contains(&words[k - 1])) || rest.trim_start().starts_with('(')
+    })
+}
+
+/// Byte range of `def name` or `class name` with its decorators and body.
+fn python_symbol(text: &str, name: &str) -> Option<(usize, usize)> {
+    let masked = code_only(text, LangFamily::Python);
+    let lines = logical_lines(text, &masked);
+    let offset = |line: &LogicalLine| line.text.as_ptr() as usize - text.as_ptr() as usize;
+
+    let (i, header) = lines.iter().enumerate().find(|(_, line)| {
+        ["def", "class"].iter().any(|keyword| {
+            let code = line.code.strip_prefix("async ").unwrap_or(line.code);
+            line.starts_with_keyword(keyword)
+                && code[keyword.len()..]
+                    .trim_start()
+                    .strip_prefix(name)
+                    .is_some_and(|rest| rest.trim_start().starts_with(['(', ':']))
+        })
+    })?;
+
+    let first = lines[..i]
+        .iter()
+        .rposition(|l| !(l.code.starts_with('@') && l.indent == header.indent))
+        .map_or(0, |p| p + 1);
+    let body = lines[i + 1..]
+        .iter()
+        .take_while(|l| l.is_blank() || l.indent > header.indent)
+        .count();
+    let last = lines[i + 1..i + 1 + body]
+        .iter()
+        .rposition(|l| !l.is_blank())
+        .map_or(i, |p| i + 1 + p);
+    Some((
+        offset(&lines[first]),
+        offset(&lines[last]) + lines[last].text.len(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skeleton_brace_languages() {
+        let rust = r#"use std::fmt::{self, Display};
+
+/// A point.
+pub struct Point {
+    pub x: i32,
+}
+
+impl Point {
+    /// Creates a point.
+    pub fn new(x: i32) -> Self {
+        // "}" inside a comment and a string
+        let s = "{";
+        Self { x }
+    }
+}
+
+trait Shape {
+    fn area(&self) -> f64;
+    fn name(&self) -> &str { "shape" }
+}
+"#;
+        let expected = r#"use std::fmt::{self, Display};
+
+/// A point.
+pub struct Point {
+    pub x: i32,
+}
+
+impl Point {
+    /// Creates a point.
+    pub fn new(x: i32) -> Self { ... }
+}
+
+trait Shape {
+    fn area(&self) -> f64;
+    fn name(&self) -> &str { ... }
+}
+"#;
+        assert_eq!(skeletonize(rust, "rs").unwrap(), expected);
+
+        let ts = "import { a } from \"b\";\nexport class Foo extends Bar {\n  run(x: number): void {\n    if (x) { a(); }\n  }\n}\nexport const f = (n) => {\n  return n;\n};\ninterface Opts {\n  debug: boolean;\n}\n";
+        let expected_ts = "import { a } from \"b\";\nexport class Foo extends Bar {\n  run(x: number): void { ... }\n}\nexport const f = (n) => { ... };\ninterface Opts {\n  debug: boolean;\n}\n";
+        assert_eq!(skeletonize(ts, "ts").unwrap(), expected_ts);
+
+        let go = "// Server serves.\ntype Server struct {\n\tAddr string\n}\n\n// Start runs it.\nfunc (s *Server) Start() error {\n\treturn nil\n}\n";
+        let expected_go = "// Server serves.\ntype Server struct {\n\tAddr string\n}\n\n// Start runs it.\nfunc (s *Server) Start() error { ... }\n";
+        assert_eq!(skeletonize(go, "go").unwrap(), expected_go);
+    }
+
+    #[test]
+    fn test_skeleton_python() {
+        let py = r#"import os
+
+class Repo(Base):
+    """Stores things."""
+
+    name: str = "repo"
+
+    @property
+    def path(
+        self,
+    ) -> str:
+        """Location on disk."""
+        # helper
+        return os.path.join(self.root, self.name)
+
+    async def fetch(self):
+        data = {
+            "a": 1,
+        }
+        return data
+
+if __name__ == "__main__":
+    main()
+"#;
+        let expected = r#"import os
+
+class Repo(Base):
+    """Stores things."""
+
+    name: str = "repo"
+
+    @property
+    def path(
+        self,
+    ) -> str:
+        """Location on disk."""
+        ...
+
+    async def fetch(self):
+        ...
+"#;
+        assert_eq!(skeletonize(py, "py").unwrap(), expected);
+        assert_eq!(skeletonize("echo hi\n", "sh"), None);
+    }
+
+    #[test]
+    fn test_symbol_lines() {
+        let rust = "use std::fmt;\n\n/// Runs.\n#[inline]\npub fn run() {\n    let s = \"fn stop() {\";\n}\n\nimpl Job {\n    fn stop(&self) {}\n}\n";
+        let lines = |name| symbol_lines(rust, "rs", name).map(|r| (r.start, r.end));
+        assert_eq!(lines("run"), Some((3, 7)));
+        assert_eq!(lines("stop"), Some((10, 10)));
+        assert_eq!(lines("Job"), Some((9, 11)));
+        assert_eq!(lines("fmt"), None);
+
+        let go = "package main\n\nfunc (s *Server) Start() error {\n\treturn nil\n}\n";
+        let found = symbol_lines(go, "go", "Start").unwrap();
+        assert_eq!((found.start, found.end), (3, 5));
+
+        let python = "import os\n\n@cached\ndef load(path):\n    x = 1\n\n    return x\n\ndef other():\n    pass\n";
+        let found = symbol_lines(python, "py", "load").unwrap();
+        assert_eq!((found.start, found.end), (3, 7));
+        assert_eq!(symbol_lines(python, "sh", "load"), None);
+    }
+}
@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::core::content::{ChunkInfo, ContentMode, ContentType, FileContext};
+use crate::core::file::LineRange;
+use crate::ports::tokenizer::TokenCounter;
+
+const CONTINUED_FROM: &str = "[... continued from previous chunk ...]\n";
+const CONTINUES_IN: &str = "[... continues in next chunk ...]\n";
+
+/// Packs files, in order, into parts whose rendered size fits in `max_tokens`.
+///
+/// `overhead` is the size of a part without files (tree, metadata, wrappers) and
+/// `cost` the size a file adds to a part once rendered. A file is never split
+/// across parts unless it alone exceeds the budget; such a file is cut at line
+/// boundaries into chunks with continuation markers, and every chunk gets a part
+/// of its own (the last one may share its part with later files).
+///
+/// Every part stands on its own: a duplicate or collapsed sibling goes into the
+/// part of the file it points to, and gets its own body back when the two don't
+/// fit in one part.
+pub fn split_into_parts(
+    files: Vec<FileContext>,
+    max_tokens: usize,
+    overhead: usize,
+    cost: &dyn Fn(&FileContext) -> usize,
+    counter: &dyn TokenCounter,
+) -> Vec<Vec<FileContext>> {
+    let mut packer = Packer {
+        budget: max_tokens.saturating_sub(overhead),
+        cost,
+        counter,
+        parts: Vec::new(),
+        current: Vec::new(),
+        used: 0,
+    };
+
+    for group in group_references(files) {
+        let group_cost: usize = group.iter().map(cost).sum();
+        if group.len() > 1 && group_cost <= packer.budget {
+            if packer.used + group_cost > packer.budget {
+                packer.next_part();
+            }
+            for file in group {
+                packer.push(file);
+            }
+            continue;
+        }
+
+        let mut group = group.into_iter();
+        let Some(head) = group.next() else {
+            continue;
+        };
+        let mut bodies: HashMap<PathBuf, (ContentType, usize)> = HashMap::new();
+        bodies.insert(
+            head.relative_path.clone(),
+            (head.content.clone(), head.token_count),
+        );
+        packer.push(head);
+        for mut file in group {
+            if let Some(similar_to) = file.similar_to.take() {
+                let (text, tokens) = similar_to.body;
+                file.content = ContentType::Text(text);
+                file.token_count = tokens;
+                file.diff = None;
+                file.mode = ContentMode::Full;
+            }
+            let body = file
+                .duplicate_of
+                .as_ref()
+                .and_then(|path| bodies.get(path))
+                .cloned();
+            if let Some((content, tokens)) = body {
+                file.content = content;
+                file.token_count = tokens;
+                file.duplicate_of = None;
+            }
+            bodies.insert(
+                file.relative_path.clone(),
+                (file.content.clone(), file.token_count),
+            );
+            packer.push(file);
+        }
+    }
+
+    packer.finish()
+}
+
+/// Groups every file with the duplicates and collapsed siblings that point to
+/// it, in the order of the files they point to.
+fn group_references(files: Vec<FileContext>) -> Vec<Vec<FileContext>> {
+    let index: HashMap<&PathBuf, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| (&file.relative_path, i))
+        .collect();
+    let reference = |i: usize| {
+        let file = &files[i];
+        file.duplicate_of
+            .as_ref()
+            .or(file.similar_to.as_ref().map(|s| &s.path))
+            .and_then(|path| index.get(path).copied())
+            .filter(|&r| r < i)
+    };
+    // References always point to an earlier file, so each root is seen first.
+    let mut roots: Vec<usize> = Vec::with_capacity(files.len());
+    for i in 0..files.len() {
+        let root = reference(i).map_or(i, |r| roots[r]);
+        roots.push(root);
+    }
+
+    let mut groups: Vec<Vec<FileContext>> = Vec::new();
+    let mut group_of: HashMap<usize, usize> = HashMap::new();
+    for (file, root) in files.into_iter().zip(roots) {
+        match group_of.get(&root) {
+            Some(&g) => groups[g].push(file),
+            None => {
+                group_of.insert(root, groups.len());
+                groups.push(vec![file]);
+            }
+        }
+    }
+    groups
+}
+
+/// Parts filled so far and the one being filled.
+struct Packer<'a> {
+    budget: usize,
+    cost: &'a dyn Fn(&FileContext) -> usize,
+    counter: &'a dyn TokenCounter,
+    parts: Vec<Vec<FileContext>>,
+    current: Vec<FileContext>,
+    used: usize,
+}
+
+impl Packer<'_> {
+    fn push(&mut self, file: FileContext) {
+        let file_cost = (self.cost)(&file);
+        if file_cost > self.budget {
+            // The file's wrapper (path, fences, tags) comes out of the body budget.
+            let wrapper = file_cost.saturating_sub(file.token_count);
+            let mut chunks = chunk_file(&file, self.budget.saturating_sub(wrapper), self.counter);
+            self.next_part();
+            let last = chunks.pop();
+            self.parts.extend(chunks.into_iter().map(|c| vec![c]));
+            if let Some(last) = last {
+                self.used = (self.cost)(&last);
+                self.current.push(last);
+            }
+            return;
+        }
+
+        if self.used + file_cost > self.budget {
+            self.next_part();
+        }
+        self.used += file_cost;
+        self.current.push(file);
+    }
+
+    fn next_part(&mut self) {
+        if !self.current.is_empty() {
+            self.parts.push(std::mem::take(&mut self.current));
+        }
+        self.used = 0;
+    }
+
+    fn finish(mut self) -> Vec<Vec<FileContext>> {
+        if !self.current.is_empty() || self.parts.is_empty() {
+            self.parts.push(self.current);
+        }
+        self.parts
+    }
+}
+
+/// Cuts an oversized text file into line-aligned chunks of at most `max_tokens`.
+///
+/// Each chunk records its own line range; a `--diff` patch rides on the first
+/// chunk only and is counted in its size.
+fn chunk_file(
+    file: &FileContext,
+    max_tokens: usize,
+    counter: &dyn TokenCounter,
+) -> Vec<FileContext> {
+    let text = match &file.content {
+        ContentType::Text(text) if !text.is_empty() => text,
+        // Bodyless files (diff only, excerpts) can't be cut; they stay whole.
+        _ => return vec![file.clone()],
+    };
+
+    let marker_tokens = counter.count(CONTINUED_FROM) + counter.count(CONTINUES_IN);
+    let budget = max_tokens.saturating_sub(marker_tokens).max(1);
+    let diff_tokens = file.diff.as_deref().map_or(0, |diff| counter.count(diff));
+
+    // Each chunk body with the index of its first line and its byte offset.
+    let mut bodies: Vec<(usize, usize, String)> = Vec::new();
+    let mut body = String::new();
+    let (mut body_line, mut body_byte) = (0, 0);
+    // The first chunk also carries the diff.
+    let mut body_tokens = diff_tokens;
+    let mut offset = 0;
+    for (index, raw) in text.split_inclusive('\n').enumerate() {
+        let line = raw.strip_suffix('\n').unwrap_or(raw);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line_tokens = counter.count(line) + 1;
+        if body_tokens + line_tokens > budget && !body.is_empty() {
+            bodies.push((body_line, body_byte, std::mem::take(&mut body)));
+            (body_line, body_byte) = (index, offset);
+            body_tokens = 0;
+        }
+        body.push_str(line);
+        body.push('\n');
+        body_tokens += line_tokens;
+        offset += raw.len();
+    }
+    if !body.is_empty() {
+        bodies.push((body_line, body_byte, body));
+    }
+
+    let first_line = file.lines.map_or(1, |lines| lines.start);
+    let total = bodies.len();
+    bodies
+        .into_iter()
+        .enumerate()
+        .map(|(i, (line, start_byte, body))| {
+            let mut chunk_text = String::with_capacity(body.len() + 80);
+            if i > 0 {
+                chunk_text.push_str(CONTINUED_FROM);
+            }
+            chunk_text.push_str(&body);
+            if i + 1 < total {
+                chunk_text.push_str(CONTINUES_IN);
+            }
+
+            let mut chunk = file.clone();
+            chunk.token_count = counter.count(&chunk_text);
+            if i == 0 {
+                chunk.token_count += diff_tokens;
+            } else {
+                chunk.diff = None;
+            }
+            let start = first_line + line;
+            chunk.lines = Some(LineRange {
+                start,
+                end: start + body.matches('\n').count() - 1,
+            });
+            chunk.start_byte = file.start_byte + start_byte;
+            chunk.content = ContentType::Text(chunk_text);
+            chunk.chunk = Some(ChunkInfo {
+                index: i + 1,
+                total,
+            });
+            chunk
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::content::SimilarTo;
+    use std::path::Path;
+
+    /// Counts one token per whitespace-separated word.
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+
+        fn name(&self) -> &str {
+            "words"
+        }
+    }
+
+    fn file(path: &str, text: String) -> FileContext {
+        let tokens = WordCounter.count(&text);
+        FileContext::new(
+            PathBuf::from(path),
+            PathBuf::from(path),
+            ContentType::Text(text),
+            "rs".into(),
+            tokens,
+        )
+    }
+
+    #[test]
+    fn test_split_keeps_files_whole() {
+        let files = vec![
+            file("a.rs", "one two three".into()),
+            file("b.rs", "four five".into()),
+            file("c.rs", "six seven eight".into()),
+        ];
+
+        let parts = split_into_parts(files, 5, 0, &|f| f.token_count, &WordCounter);
+        let names: Vec<Vec<_>> = parts
+            .iter()
+            .map(|p| p.iter().map(|f| f.relative_path.clone()).collect())
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")],
+                vec![PathBuf::from("c.rs")],
+            ]
+        );
+        assert!(parts.iter().flatten().all(|f| f.chunk.is_none()));
+    }
+
+    #[test]
+    fn test_split_counts_part_overhead() {
+        let files = vec![
+            file("a.rs", "one two three".into()),
+            file("b.rs", "four five".into()),
+            file("c.rs", "six seven eight".into()),
+        ];
+
+        // 3 tokens of tree and metadata per part, 1 token of wrapper per file.
+        let parts = split_into_parts(files, 10, 3, &|f| f.token_count + 1, &WordCounter);
+        let sizes: Vec<usize> = parts.iter().map(|p| p.len()).collect();
+
+        assert_eq!(sizes, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_split_keeps_duplicates_with_their_original() {
+        let mut copy = file("b/x.rs", String::new());
+        copy.duplicate_of = Some(PathBuf::from("a/x.rs"));
+        let files = vec![
+            file("a/x.rs", "one two three".into()),
+            file("filler.rs", "four five six seven".into()),
+            copy,
+        ];
+        let names = |parts: &[Vec<FileContext>]| -> Vec<Vec<String>> {
+            parts
+                .iter()
+                .map(|p| {
+                    p.iter()
+                        .map(|f| f.relative_path.to_string_lossy().into_owned())
+                        .collect()
+                })
+                .collect()
+        };
+
+        // 1 token of wrapper per file: the original and its copy fit in 5.
+        let parts = split_into_parts(files.clone(), 5, 0, &|f| f.token_count + 1, &WordCounter);
+        assert_eq!(
+            names(&parts),
+            vec![vec!["a/x.rs", "b/x.rs"], vec!["filler.rs"]]
+        );
+        assert!(parts[0][1].duplicate_of.is_some());
+
+        // In 4 they can't share a part, so the copy carries the body itself.
+        let parts = split_into_parts(files, 4, 0, &|f| f.token_count + 1, &WordCounter);
+        let copy = parts
+            .iter()
+            .flatten()
+            .find(|f| f.relative_path == Path::new("b/x.rs"));
+        let copy = copy.expect("The copy is still in the report");
+        assert_eq!(copy.duplicate_of, None);
+        assert_eq!(copy.content, ContentType::Text("one two three".into()));
+        assert_eq!(copy.token_count, 3);
+    }
+
+    #[test]
+    fn test_split_keeps_siblings_with_their_representative() {
+        let mut sibling = file("b/y.rs", String::new());
+        sibling.mode = ContentMode::Diff;
+        sibling.diff = Some("-two +deux".into());
+        sibling.token_count = 2;
+        sibling.similar_to = Some(SimilarTo {
+            path: PathBuf::from("a/y.rs"),
+            similarity: 0.9,
+            body: ("one deux three".into(), 3),
+        });
+        let files = vec![
+            file("a/y.rs", "one two three".into()),
+            file("filler.rs", "four five six seven".into()),
+            sibling,
+        ];
+
+        let parts = split_into_parts(files.clone(), 6, 0, &|f| f.token_count, &WordCounter);
+        assert_eq!(parts[0].len(), 2);
+        assert_eq!(parts[0][1].relative_path, Path::new("b/y.rs"));
+        assert!(parts[0][1].similar_to.is_some());
+
+        // In 4 they can't share a part, so the sibling is emitted in full.
+        let parts = split_into_parts(files, 4, 0, &|f| f.token_count, &WordCounter);
+        let sibling = parts
+            .iter()
+            .flatten()
+            .find(|f| f.relative_path == Path::new("b/y.rs"));
+        let sibling = sibling.expect("The sibling is still in the report");
+        assert_eq!(sibling.similar_to, None);
+        assert_eq!(sibling.diff, None);
+        assert_eq!(sibling.mode, ContentMode::Full);
+        assert_eq!(sibling.content, ContentType::Text("one deux three".into()));
+    }
+
+    #[test]
+    fn test_split_chunks_oversized_file() {
+        let big: String = (1..=30).map(|i| format!("w{}\n", i)).collect();
+        let files = vec![file("small.rs", "a b".into()), file("big.rs", big)];
+
+        let parts = split_into_parts(files, 20, 0, &|f| f.token_count, &WordCounter);
+
+        assert_eq!(
+            parts[0].len(),
+            1,
+            "small.rs must not share a part with a chunk"
+        );
+        let chunks: Vec<&FileContext> = parts[1..].iter().flatten().collect();
+        assert!(chunks.len() > 1);
+        for (i, chunk) in chunks.iter().enumerate() {
+            let info = chunk.chunk.expect("Chunk info");
+            assert_eq!(info.index, i + 1);
+            assert_eq!(info.total, chunks.len());
+            assert!(chunk.token_count <= 20);
+        }
+
+        let ContentType::Text(first) = &chunks[0].content else {
+            panic!("Should stay text");
+        };
+        assert!(first.starts_with("w1\n"));
+        assert!(first.ends_with(CONTINUES_IN));
+    }
+
+    #[test]
+    fn test_split_chunks_keep_their_lines_and_one_diff() {
+        let body: String = (10..=39).map(|i| format!("w{}\n", i)).collect();
+        let mut big = file("big.rs", body);
+        big.lines = Some(LineRange { start: 10, end: 39 });
+        big.diff = Some("d1 d2 d3 d4 d5".into());
+        big.token_count += 5;
+
+        let parts = split_into_parts(vec![big], 20, 0, &|f| f.token_count, &WordCounter);
+        let chunks: Vec<&FileContext> = parts.iter().flatten().collect();
+
+        assert!(chunks[0].diff.is_some());
+        assert!(chunks[1..].iter().all(|c| c.diff.is_none()));
+        assert!(chunks.iter().all(|c| c.token_count <= 20));
+        let ranges: Vec<LineRange> = chunks.iter().map(|c| c.lines.unwrap()).collect();
+        assert_eq!(ranges[0].start, 10);
+        assert_eq!(ranges[ranges.len() - 1].end, 39);
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[1].start, pair[0].end + 1);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::core::content::{ContentType, FileContext};
+use crate::ports::tokenizer::TokenCounter;
+
+/// Caps a text file at `max_tokens` by keeping a head and a tail of whole lines
+/// and replacing the middle with an elision marker.
+///
+/// Returns `true` when the file was truncated. Non-text content and files
+/// already within the cap are left untouched.
+pub fn cap_file_tokens(
+    file: &mut FileContext,
+    max_tokens: usize,
+    counter: &dyn TokenCounter,
+) -> bool {
+    if file.token_count <= max_tokens {
+        return false;
+    }
+    let ContentType::Text(text) = &file.content else {
+        return false;
+    };
+
+    let lines: Vec<&str> = text.lines().collect();
+    let line_tokens: Vec<usize> = lines.iter().map(|l| counter.count(l) + 1).collect();
+    let half = max_tokens / 2;
+
+    let mut head_end = 0;
+    let mut head_tokens = 0;
+    while head_end < lines.len() && head_tokens + line_tokens[head_end] <= half {
+        head_tokens += line_tokens[head_end];
+        head_end += 1;
+    }
+
+    let mut tail_start = lines.len();
+    let mut tail_tokens = 0;
+    while tail_start > head_end && tail_tokens + line_tokens[tail_start - 1] <= half {
+        tail_tokens += line_tokens[tail_start - 1];
+        tail_start -= 1;
+    }
+
+    let elided_lines = tail_start - head_end;
+    let elided_tokens: usize = line_tokens[head_end..tail_start].iter().sum();
+
+    // Not even one whole line fits (minified code, a JSON blob): cut inside
+    // the text instead.
+    if head_end == 0 && tail_start == lines.len() {
+        let capped = cap_chars(text, half, counter);
+        file.token_count = counter.count(&capped);
+        file.content = ContentType::Text(capped);
+        file.truncated = true;
+        return true;
+    }
+
+    let mut capped = String::with_capacity(text.len() / 2);
+    for line in &lines[..head_end] {
+        capped.push_str(line);
+        capped.push('\n');
+    }
+    capped.push_str(&format!(
+        "[... {} lines / ~{} tokens elided ...]\n",
+        elided_lines, elided_tokens
+    ));
+    for line in &lines[tail_start..] {
+        capped.push_str(line);
+        capped.push('\n');
+    }
+
+    file.token_count = counter.count(&capped);
+    file.content = ContentType::Text(capped);
+    file.truncated = true;
+    true
+}
+
+/// Keeps the longest head and tail of `text`, cut at character boundaries,
+/// that fit in `half` tokens each.
+fn cap_chars(text: &str, half: usize, counter: &dyn TokenCounter) -> String {
+    let bounds: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    // Token counts grow with the slice, so both cuts can be binary searched.
+    let head_end = bounds[bounds.partition_point(|&end| counter.count(&text[..end]) <= half) - 1];
+    let tail_start =
+        bounds[bounds.partition_point(|&start| counter.count(&text[start..]) > half)].max(head_end);
+
+    let elided = &text[head_end..tail_start];
+    format!(
+        "{}\n[... {} characters / ~{} tokens elided ...]\n{}\n",
+        &text[..head_end],
+        elided.chars().count(),
+        counter.count(elided),
+        text[tail_start..].trim_end_matches('\n')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Counts one token per whitespace-separated word.
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+
+        fn name(&self) -> &str {
+            "words"
+        }
+    }
+
+    fn text_file(text: String) -> FileContext {
+        let tokens = WordCounter.count(&text);
+        FileContext::new(
+            PathBuf::from("gen.rs"),
+            PathBuf::from("gen.rs"),
+            ContentType::Text(text),
+            "rs".into(),
+            tokens,
+        )
+    }
+
+    #[test]
+    fn test_cap_keeps_head_and_tail() {
+        let text: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+        let mut file = text_file(text);
+
+        assert!(cap_file_tokens(&mut file, 30, &WordCounter));
+        assert!(file.truncated);
+
+        let ContentType::Text(capped) = &file.content else {
+            panic!("Should stay text");
+        };
+        // Each line costs 2 words + 1 for the newline: 5 lines fit in each half.
+        assert!(capped.starts_with("line 1\n"));
+        assert!(capped.contains("line 5\n[... 90 lines / ~270 tokens elided ...]\nline 96\n"));
+        assert!(capped.ends_with("line 100\n"));
+    }
+
+    #[test]
+    fn test_cap_single_long_line() {
+        let text: String = (1..=100).map(|i| format!("w{} ", i)).collect();
+        let mut file = text_file(text);
+
+        assert!(cap_file_tokens(&mut file, 10, &WordCounter));
+        let ContentType::Text(capped) = &file.content else {
+            panic!("Should stay text");
+        };
+        assert!(capped.starts_with("w1 w2 w3 w4 w5"));
+        assert!(capped.contains("elided ...]\n"));
+        assert!(capped.ends_with("w96 w97 w98 w99 w100 \n"));
+    }
+
+    #[test]
+    fn test_cap_ignores_small_files() {
+        let mut file = text_file("fn main() {}\n".into());
+        assert!(!cap_file_tokens(&mut file, 100, &WordCounter));
+        assert!(!file.truncated);
+    }
+}
@@ -1,39 +1,70 @@
 //! Entry point for the Context Engine CLI.
 
 use arboard::Clipboard;
-use clap::Parser;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use clap::{ArgGroup, Parser};
 use rayon::prelude::*;
+use regex::Regex;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufWriter, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::{error, info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 
 use context::adapters::fs_reader::FsReader;
 use context::adapters::fs_scanner::FsScanner;
+use context::adapters::git;
+use context::adapters::git_reader::GitReader;
+use context::adapters::git_scanner::GitScanner;
+use context::adapters::list_scanner::ListScanner;
 use context::adapters::output::json::JsonWriter;
+use context::adapters::output::jsonl::JsonlChunkWriter;
 use context::adapters::output::markdown::MarkdownWriter;
 use context::adapters::output::text::TextWriter;
 use context::adapters::output::xml::XmlWriter;
-use context::core::config::{ContextConfig, OutputFormat};
+use context::adapters::project_config::ProjectConfig;
+use context::adapters::tokenizer::BpeTokenCounter;
+use context::core::budget::apply_token_budget;
+use context::core::collapse::collapse_similar;
+use context::core::config::{
+    ContextConfig, DiffMode, GitSelection, OutputFormat, OversizePolicy, ProjectRoot, RelatedFiles,
+    TimeFilter, TokenizerKind, DEFAULT_CHUNK_OVERLAP, DEFAULT_CHUNK_TOKENS, DEFAULT_DIFF_CONTEXT,
+};
+use context::core::content::{Fidelity, FileContext};
+use context::core::dedup::dedupe_files;
+use context::core::fidelity::apply_fidelity;
+use context::core::file::parse_path_spec;
+use context::core::grep::grep_file;
+use context::core::noise::NoiseRules;
+use context::core::patch::attach_diffs;
+use context::core::pattern::PathMatcher;
+use context::core::report::{OmissionReason, OmittedFile, PartInfo, ReportMeta};
+use context::core::skeleton::apply_skeleton;
+use context::core::split::split_into_parts;
+use context::core::truncate::cap_file_tokens;
 use context::ports::reader::FileReader;
 use context::ports::scanner::ProjectScanner;
+use context::ports::tokenizer::TokenCounter;
 use context::ports::writer::ContextWriter;
 use context::ui::run_tui;
 
 /// High-performance AI Context Generator.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(group(ArgGroup::new("git_selection").multiple(true)))]
 struct Cli {
-    /// Path to the project root to scan.
-    #[arg(default_value = ".")]
-    path: PathBuf,
+    /// Project roots to scan; several roots share one report, each under its own label.
+    /// Files can be given instead, whole or as `path:START-END` / `path#symbol`.
+    #[arg(default_value = ".", num_args = 1..)]
+    paths: Vec<PathBuf>,
 
     /// Optional output file path.
     #[arg(short, long)]
     output: Option<PathBuf>,
 
-    /// Output format (xml, markdown, json).
+    /// Output format (xml, markdown, json, text, jsonl-chunks).
     #[arg(short, long, value_enum, default_value_t = OutputFormat::Xml)]
     format: OutputFormat,
 
@@ -45,6 +76,155 @@ struct Cli {
     #[arg(short = 'm', long, default_value_t = false)]
     minify: bool,
 
+    /// BPE encoding used to count tokens.
+    #[arg(long, value_enum, default_value_t = TokenizerKind::O200k)]
+    tokenizer: TokenizerKind,
+
+    /// Hard token budget; files that do not fit are left out and listed in the metadata.
+    #[arg(long, value_name = "N")]
+    max_tokens: Option<usize>,
+
+    /// Per-file token cap; larger text files keep only their head and tail.
+    #[arg(long, value_name = "N")]
+    max_file_tokens: Option<usize>,
+
+    /// Split the report into numbered parts of at most N tokens each (tree and
+    /// metadata included; a single oversized line can still exceed it).
+    #[arg(long, value_name = "N")]
+    split_tokens: Option<usize>,
+
+    /// Target chunk size in tokens for the jsonl-chunks format.
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_CHUNK_TOKENS)]
+    chunk_tokens: usize,
+
+    /// Tokens repeated between consecutive chunks in the jsonl-chunks format.
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_CHUNK_OVERLAP)]
+    chunk_overlap: usize,
+
+    /// Remove line and block comments (Rust, C/C++, Java, JS/TS, Go, Python, shell, SQL).
+    #[arg(long, default_value_t = false)]
+    strip_comments: bool,
+
+    /// Keep doc comments when stripping comments.
+    #[arg(long, default_value_t = false, requires = "strip_comments")]
+    keep_doc_comments: bool,
+
+    /// Replace function bodies with `{ ... }`, keeping signatures, type definitions and doc comments.
+    #[arg(long, default_value_t = false)]
+    skeleton: bool,
+
+    /// Full text only for files matching this gitignore-style glob (repeatable);
+    /// the rest are outlined (signatures, Markdown headings) or just listed.
+    #[arg(long, value_name = "GLOB", conflicts_with = "skeleton")]
+    focus: Vec<String>,
+
+    /// Keep every copy of files with identical content instead of referencing the first one.
+    #[arg(long, default_value_t = false)]
+    no_dedup: bool,
+
+    /// Collapse near-identical files (line-set similarity >= THRESHOLD, 0.0-1.0) into one
+    /// representative plus a unified diff per sibling.
+    #[arg(long, value_name = "THRESHOLD", value_parser = parse_similarity)]
+    collapse_similar: Option<f64>,
+
+    /// Files larger than this (e.g. 512K, 10MB) are skipped, or truncated with --oversize truncate.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_file_size: Option<u64>,
+
+    /// What to do with text files over --max-file-size.
+    #[arg(long, value_enum, default_value_t = OversizePolicy::Skip, requires = "max_file_size")]
+    oversize: OversizePolicy,
+
+    /// Follow symbolic links (loops are detected; files reachable through several links appear once).
+    #[arg(long, default_value_t = false)]
+    follow_symlinks: bool,
+
+    /// Convert CRLF line endings to LF.
+    #[arg(long, default_value_t = false)]
+    normalize_eol: bool,
+
+    /// Only files changed between this git revision and the working tree (untracked included).
+    #[arg(long, value_name = "REV", group = "git_selection")]
+    changed_since: Option<String>,
+
+    /// Only files staged in the git index.
+    #[arg(long, default_value_t = false, group = "git_selection")]
+    staged: bool,
+
+    /// Only files with staged, unstaged or untracked changes.
+    #[arg(long, default_value_t = false, group = "git_selection")]
+    uncommitted: bool,
+
+    /// Only files changed in this git range (`A..B`, `A...B`, or `A` against the working tree),
+    /// each with its unified diff.
+    #[arg(long, value_name = "RANGE", group = "git_selection")]
+    diff: Option<String>,
+
+    /// With --diff: the changed lines only, the diff with --diff-context lines, or the diff plus the whole file.
+    #[arg(long, value_enum, default_value_t = DiffMode::Full, requires = "diff")]
+    diff_mode: DiffMode,
+
+    /// Unchanged lines around each --diff hunk (ignored by --diff-mode only).
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_DIFF_CONTEXT, requires = "diff")]
+    diff_context: u32,
+
+    /// Scan and read a git revision (commit, tag, branch) instead of the working tree.
+    #[arg(
+        long,
+        value_name = "REV",
+        conflicts_with_all = ["git_selection", "follow_symlinks", "show_noise"]
+    )]
+    rev: Option<String>,
+
+    /// Package exactly the files listed in PATH (`-` for stdin): newline- or NUL-separated,
+    /// each optionally followed by `:START-END` to keep only those lines.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["rev", "git_selection", "follow_symlinks", "show_noise"]
+    )]
+    files_from: Option<String>,
+
+    /// Only files whose content matches this regular expression.
+    #[arg(long, value_name = "REGEX", value_parser = parse_regex)]
+    grep: Option<String>,
+
+    /// With --grep: only the matching lines plus N lines around each, instead of whole files.
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "grep",
+        conflicts_with_all = ["skeleton", "focus", "max_file_tokens", "diff"]
+    )]
+    grep_context: Option<usize>,
+
+    /// Only files modified within this duration (`90m`, `2h`, `3d`, `1w`) or since this date
+    /// (`today`, `yesterday`, `2024-05-01`, `2024-05-01T14:30`).
+    #[arg(
+        long,
+        value_name = "WHEN",
+        value_parser = parse_time,
+        conflicts_with_all = ["rev", "files_from"]
+    )]
+    newer_than: Option<DateTime<Utc>>,
+
+    /// Only files last modified before this duration ago or this date (same syntax as --newer-than).
+    #[arg(
+        long,
+        value_name = "WHEN",
+        value_parser = parse_time,
+        conflicts_with_all = ["rev", "files_from"]
+    )]
+    older_than: Option<DateTime<Utc>>,
+
+    /// Use the time of the last commit touching each file instead of its file system mtime.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["rev", "files_from"])]
+    git_mtime: bool,
+
+    /// Also include files related to the git selection (comma separated).
+    #[arg(long, value_enum, value_delimiter = ',', requires = "git_selection")]
+    with_related: Vec<RelatedFiles>,
+
     /// Interactive mode (TUI) to select files manually.
     #[arg(short = 'I', long, default_value_t = false)]
     interactive: bool,
@@ -57,10 +237,25 @@ struct Cli {
     #[arg(long, default_value_t = false)]
     include_hidden: bool,
 
-    /// Ignore gitignore and .ignore files.
+    /// Ignore gitignore and .ignore files (.contextignore files still apply).
     #[arg(long, default_value_t = false)]
     no_ignore: bool,
 
+    /// Extra ignore file with gitignore syntax, relative to the project root, or to
+    /// each root when several are given (repeatable).
+    /// Like `.contextignore` files, it applies even with --no-ignore.
+    #[arg(long, value_name = "PATH")]
+    ignore_file: Vec<PathBuf>,
+
+    /// Do not drop the built-in noise (lock files, dependency and build directories);
+    /// only the names listed in .context.toml apply.
+    #[arg(long, default_value_t = false)]
+    no_default_noise: bool,
+
+    /// List the files and directories dropped as noise, then exit.
+    #[arg(long, default_value_t = false)]
+    show_noise: bool,
+
     /// Filter by extension (comma separated).
     #[arg(short = 'e', long, value_delimiter = ',')]
     extensions: Vec<String>,
@@ -69,11 +264,12 @@ struct Cli {
     #[arg(short = 'x', long, value_delimiter = ',')]
     exclude_extensions: Vec<String>,
 
-    /// Only include paths containing this string.
+    /// Only include paths matching this gitignore-style glob, relative to the root
+    /// (repeatable, `!` negates: `-i 'src/**/*.rs' -i '!src/generated/**'`).
     #[arg(short = 'i', long)]
     include_path: Vec<String>,
 
-    /// Exclude paths containing this string.
+    /// Exclude paths matching this gitignore-style glob, relative to the root (repeatable).
     #[arg(short = 'X', long)]
     exclude_path: Vec<String>,
 
@@ -83,30 +279,155 @@ struct Cli {
 }
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
     init_logging(cli.verbose);
 
     info!("Starting Context Engine...");
 
-    let mut config = ContextConfig::new(
-        cli.path,
-        cli.output.clone(),
-        cli.format,
-        cli.depth,
-        cli.include_hidden,
-        cli.no_ignore,
-        cli.clip,
-        cli.minify,
-        cli.verbose > 0,
-        cli.extensions,
-        cli.exclude_extensions,
-        cli.include_path,
-        cli.exclude_path,
-    );
+    // File arguments are read like `--files-from` entries, relative to the
+    // current directory, instead of being scanned as roots.
+    let specs: Vec<_> = cli
+        .paths
+        .iter()
+        .map(|path| parse_path_spec(&path.to_string_lossy()))
+        .collect();
+    let selected = specs
+        .iter()
+        .filter(|(path, fragment)| fragment.is_some() || path.is_file())
+        .count();
+    let file_args = match selected {
+        0 => None,
+        n if n < specs.len() => anyhow::bail!("file arguments can't be mixed with directories"),
+        _ => {
+            // Options of the directory scan, which file arguments skip.
+            let scan_options = [
+                ("--rev", cli.rev.is_some()),
+                ("--files-from", cli.files_from.is_some()),
+                ("--changed-since", cli.changed_since.is_some()),
+                ("--staged", cli.staged),
+                ("--uncommitted", cli.uncommitted),
+                ("--diff", cli.diff.is_some()),
+                ("--follow-symlinks", cli.follow_symlinks),
+                ("--show-noise", cli.show_noise),
+                ("--newer-than", cli.newer_than.is_some()),
+                ("--older-than", cli.older_than.is_some()),
+                ("--git-mtime", cli.git_mtime),
+            ];
+            if let Some((flag, _)) = scan_options.iter().find(|(_, set)| *set) {
+                anyhow::bail!("file arguments can't be combined with {}", flag);
+            }
+            cli.paths = vec![PathBuf::from(".")];
+            Some(specs)
+        }
+    };
+
+    if cli.paths.len() > 1 {
+        if cli.rev.is_some() {
+            anyhow::bail!("--rev works with a single root");
+        }
+        if cli.files_from.is_some() {
+            anyhow::bail!("--files-from works with a single root");
+        }
+    }
+    let root_path = cli.paths[0].clone();
+    let mut roots = if cli.paths.len() > 1 {
+        ProjectRoot::label_all(&cli.paths)
+    } else {
+        Vec::new()
+    };
+    // Each root follows its own `.context.toml`.
+    for root in &mut roots {
+        let project_config = ProjectConfig::load(&root.path)?;
+        root.noise = NoiseRules::resolve(&project_config.noise, !cli.no_default_noise);
+    }
+
+    let project_config = ProjectConfig::load(&root_path)?;
+
+    let mut config = ContextConfig {
+        tokenizer: cli.tokenizer,
+        max_tokens: cli.max_tokens,
+        max_file_tokens: cli.max_file_tokens,
+        split_tokens: cli.split_tokens,
+        chunk_tokens: cli.chunk_tokens,
+        chunk_overlap: cli.chunk_overlap,
+        strip_comments: cli.strip_comments,
+        keep_doc_comments: cli.keep_doc_comments,
+        skeleton: cli.skeleton,
+        focus: cli.focus,
+        dedup: !cli.no_dedup,
+        collapse_similar: cli.collapse_similar,
+        ignore_files: cli.ignore_file,
+        max_file_size: cli.max_file_size,
+        oversize: cli.oversize,
+        normalize_eol: cli.normalize_eol,
+        follow_symlinks: cli.follow_symlinks,
+        git: GitSelection {
+            changed_since: cli.changed_since,
+            staged: cli.staged,
+            uncommitted: cli.uncommitted,
+            diff_range: cli.diff,
+            related: cli.with_related,
+        },
+        diff_mode: cli.diff_mode,
+        diff_context: cli.diff_context,
+        rev: cli.rev,
+        grep: cli.grep,
+        grep_context: cli.grep_context,
+        time: TimeFilter {
+            newer_than: cli.newer_than,
+            older_than: cli.older_than,
+            git_mtime: cli.git_mtime,
+        },
+        roots,
+        noise: NoiseRules::resolve(&project_config.noise, !cli.no_default_noise),
+        ..ContextConfig::new(
+            root_path,
+            cli.output.clone(),
+            cli.format,
+            cli.depth,
+            cli.include_hidden,
+            cli.no_ignore,
+            cli.clip,
+            cli.minify,
+            cli.verbose > 0,
+            cli.extensions,
+            cli.exclude_extensions,
+            cli.include_path,
+            cli.exclude_path,
+        )
+    };
+
+    for file in config.ignore_files.iter().filter(|f| f.is_relative()) {
+        if !config
+            .root_paths()
+            .iter()
+            .any(|(_, root)| root.join(file).is_file())
+        {
+            anyhow::bail!(
+                "ignore file {} not found in the project root",
+                file.display()
+            );
+        }
+    }
 
     // 1. SCANNING
+    if cli.show_noise {
+        for (path, is_dir) in FsScanner::new().list_noise(&config)? {
+            let suffix = if is_dir { "/" } else { "" };
+            println!("{}{}", path.display(), suffix);
+        }
+        return Ok(());
+    }
+
+    let scanner: Box<dyn ProjectScanner> = match (&config.rev, &cli.files_from, file_args) {
+        (_, _, Some(entries)) => Box::new(ListScanner::new(entries)),
+        (Some(rev), _, None) => Box::new(GitScanner::open(&config.root_path, rev)?),
+        (None, Some(list), None) if list == "-" => Box::new(ListScanner::from_reader(io::stdin())?),
+        (None, Some(list), None) => Box::new(ListScanner::from_reader(File::open(list)?)?),
+        (None, None, None) => Box::new(FsScanner::new()),
+    };
+
     info!("Phase 1: Scanning directory...");
-    let scanner = FsScanner::new();
     let mut files = match scanner.scan(&config) {
         Ok(f) => f,
         Err(e) => {
@@ -122,17 +443,24 @@ fn main() -> anyhow::Result<()> {
         match run_tui(&files, &config.root_path, config.clone()) {
             Ok(Some((selected_paths, new_config))) => {
                 let prev_count = files.len();
-                files.retain(|node| selected_paths.contains(&node.relative_path));
+                files.retain_mut(|node| match selected_paths.get(&node.relative_path) {
+                    Some(fragment) => {
+                        node.fragment = fragment.clone();
+                        true
+                    }
+                    None => false,
+                });
 
                 config = new_config;
 
                 info!(
-                    "Interactive selection: Kept {}/{} files. Config updated (Fmt: {:?}, Clip: {}, Min: {})",
+                    "Interactive selection: Kept {}/{} files. Config updated (Fmt: {:?}, Clip: {}, Min: {}, Strip: {})",
                     files.len(),
                     prev_count,
                     config.output_format,
                     config.to_clipboard,
-                    config.minify
+                    config.minify,
+                    config.strip_comments
                 );
             }
             Ok(None) => {
@@ -148,36 +476,161 @@ fn main() -> anyhow::Result<()> {
 
     // 2. READING
     info!("Phase 2: Reading content...");
-    let reader = FsReader::new();
-    let contexts: Vec<_> = files
+    let token_counter: Arc<dyn TokenCounter> = Arc::new(BpeTokenCounter::new(config.tokenizer));
+    let reader = FsReader::with_token_counter(token_counter.clone())
+        .with_size_limit(config.max_file_size, config.oversize)
+        .with_normalize_eol(config.normalize_eol);
+    let reader: Box<dyn FileReader> = match &config.rev {
+        Some(rev) => Box::new(GitReader::open(&config.root_path, rev, reader)?),
+        None => Box::new(reader),
+    };
+    let mut contexts: Vec<_> = files
         .par_iter()
         .map(|node| reader.read_file(node))
         .collect();
 
     let total_tokens: usize = contexts.iter().map(|c| c.token_count).sum();
     info!(
-        "Processed {} files. Total estimated tokens: {}",
+        "Processed {} files. Total tokens ({}): {}",
         contexts.len(),
+        config.tokenizer.as_str(),
         total_tokens
     );
 
+    if let Some(pattern) = &config.grep {
+        let regex = Regex::new(pattern)?;
+        contexts = contexts
+            .into_par_iter()
+            .filter_map(|mut ctx| {
+                grep_file(
+                    &mut ctx,
+                    &regex,
+                    config.grep_context,
+                    token_counter.as_ref(),
+                )
+                .then_some(ctx)
+            })
+            .collect();
+        info!("Grep `{}`: {} files match.", pattern, contexts.len());
+    }
+
+    if config.skeleton {
+        let reduced = contexts
+            .par_iter_mut()
+            .map(|ctx| apply_skeleton(ctx, token_counter.as_ref()))
+            .filter(|&r| r)
+            .count();
+        let skeleton_tokens: usize = contexts.iter().map(|c| c.token_count).sum();
+        info!(
+            "Skeleton mode: reduced {} files. Total tokens: {}",
+            reduced, skeleton_tokens
+        );
+    }
+
+    if !config.focus.is_empty() {
+        let focus = PathMatcher::new(&config.focus)?;
+        contexts.par_iter_mut().for_each(|ctx| {
+            apply_fidelity(ctx, &focus, token_counter.as_ref());
+        });
+        let full = contexts
+            .iter()
+            .filter(|c| c.fidelity == Some(Fidelity::Full))
+            .count();
+        info!(
+            "Focus: {} of {} files kept at full fidelity.",
+            full,
+            contexts.len()
+        );
+    }
+
+    if let Some(max_file_tokens) = config.max_file_tokens {
+        let truncated = contexts
+            .par_iter_mut()
+            .map(|ctx| cap_file_tokens(ctx, max_file_tokens, token_counter.as_ref()))
+            .filter(|&t| t)
+            .count();
+        info!(
+            "Per-file cap {}: truncated {} files.",
+            max_file_tokens, truncated
+        );
+    }
+
+    if let Some(range) = &config.git.diff_range {
+        let context_lines = match config.diff_mode {
+            DiffMode::Only => 0,
+            DiffMode::Context | DiffMode::Full => config.diff_context,
+        };
+        let mut diffs = HashMap::new();
+        for (label, root) in config.root_paths() {
+            for (path, diff) in git::file_diffs(root, range, context_lines)? {
+                let path = match label {
+                    Some(label) => Path::new(label).join(path),
+                    None => path,
+                };
+                diffs.insert(path, diff);
+            }
+        }
+        attach_diffs(
+            &mut contexts,
+            &diffs,
+            config.diff_mode,
+            token_counter.as_ref(),
+        );
+        info!("Diff {}: {} patches attached.", range, diffs.len());
+    }
+
+    if config.transforms_text() {
+        contexts
+            .par_iter_mut()
+            .for_each(|ctx| ctx.count_rendered(&config, token_counter.as_ref()));
+        let rendered_tokens: usize = contexts.iter().map(|c| c.token_count).sum();
+        info!(
+            "Comment stripping / minification: {} tokens as emitted.",
+            rendered_tokens
+        );
+    }
+
+    let mut meta = ReportMeta::default();
+
+    if config.dedup {
+        meta.duplicates = dedupe_files(&mut contexts);
+        info!(
+            "De-duplication: {} identical files referenced, {} tokens saved.",
+            meta.duplicates.files, meta.duplicates.tokens_saved
+        );
+    }
+
+    if let Some(threshold) = config.collapse_similar {
+        meta.similar = collapse_similar(&mut contexts, threshold, token_counter.as_ref());
+        info!(
+            "Near-duplicates: {} files collapsed into {} clusters, {} tokens saved.",
+            meta.similar.files, meta.similar.clusters, meta.similar.tokens_saved
+        );
+    }
+
+    if let Some(max_tokens) = config.max_tokens {
+        let (kept, omitted) = budget_files(contexts, &meta, max_tokens, &token_counter, &config)?;
+        info!(
+            "Token budget {}: kept {} files, omitted {}.",
+            max_tokens,
+            kept.len(),
+            omitted.len()
+        );
+        contexts = kept;
+        meta.omitted = omitted;
+    }
+
     // 3. OUTPUT
     info!("Phase 3: Generating output ({:?})...", config.output_format);
 
-    let buffer = generate_output_buffer(&contexts, &config)?;
+    if let Some(split_tokens) = config.split_tokens {
+        return write_split_output(contexts, meta, split_tokens, &token_counter, &config);
+    }
+
+    let buffer = generate_output_buffer(&contexts, &meta, &config, &token_counter)?;
 
     if config.to_clipboard {
-        let output_str = String::from_utf8(buffer.clone())?;
-        match Clipboard::new() {
-            Ok(mut clipboard) => {
-                if let Err(e) = clipboard.set_text(&output_str) {
-                    error!("Failed to copy to clipboard: {}", e);
-                } else {
-                    info!("Output copied to clipboard! ({} chars)", output_str.len());
-                }
-            }
-            Err(e) => error!("Could not access clipboard: {}", e),
-        }
+        copy_to_clipboard(&buffer)?;
 
         if let Some(path) = &config.output_path {
             let mut file = File::create(path)?;
@@ -206,34 +659,317 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Runs `--max-tokens` against the report as rendered: the wrappers, metadata,
+/// the full tree and the omitted list are paid for before any file is kept.
+fn budget_files(
+    files: Vec<FileContext>,
+    meta: &ReportMeta,
+    max_tokens: usize,
+    token_counter: &Arc<dyn TokenCounter>,
+    config: &ContextConfig,
+) -> anyhow::Result<(Vec<FileContext>, Vec<OmittedFile>)> {
+    let rendered_tokens = |files: &[FileContext], meta: &ReportMeta| -> anyhow::Result<usize> {
+        let buffer = generate_output_buffer(files, meta, config, token_counter)?;
+        Ok(token_counter.count(&String::from_utf8_lossy(&buffer)))
+    };
+    let tree: Vec<PathBuf> = files.iter().map(|f| f.relative_path.clone()).collect();
+    let base = rendered_tokens(
+        &[],
+        &ReportMeta {
+            tree: Some(tree),
+            ..meta.clone()
+        },
+    )?;
+
+    // What each file adds as an entry of the omitted list. Its tree line is
+    // already part of `base`, so it is rendered on both sides. The longer
+    // reason is assumed.
+    let mut listed = HashMap::new();
+    for file in &files {
+        let tree_meta = ReportMeta {
+            tree: Some(vec![file.relative_path.clone()]),
+            ..meta.clone()
+        };
+        let omitted_meta = ReportMeta {
+            omitted: vec![OmittedFile {
+                relative_path: file.relative_path.clone(),
+                token_count: file.token_count,
+                reason: OmissionReason::ReferenceOmitted,
+            }],
+            ..tree_meta.clone()
+        };
+        let tokens =
+            rendered_tokens(&[], &omitted_meta)?.saturating_sub(rendered_tokens(&[], &tree_meta)?);
+        listed.insert(file.relative_path.clone(), tokens);
+    }
+    let overhead = base + listed.values().sum::<usize>();
+    if overhead >= max_tokens {
+        warn!(
+            "The tree and metadata alone take ~{} tokens; the report will exceed --max-tokens {}.",
+            overhead, max_tokens
+        );
+    }
+
+    let bare_meta = ReportMeta {
+        tree: Some(Vec::new()),
+        ..meta.clone()
+    };
+    let bare = rendered_tokens(&[], &bare_meta)?;
+    let cost = |file: &FileContext| {
+        let kept = rendered_tokens(std::slice::from_ref(file), &bare_meta)
+            .map(|tokens| tokens.saturating_sub(bare))
+            .unwrap_or(file.token_count);
+        kept.saturating_sub(listed.get(&file.relative_path).copied().unwrap_or(0))
+    };
+
+    Ok(apply_token_budget(
+        files,
+        max_tokens,
+        overhead,
+        &cost,
+        config,
+        token_counter.as_ref(),
+    ))
+}
+
+/// Writes the report as self-contained numbered parts (`report.part1.xml`, ...).
+fn write_split_output(
+    files: Vec<FileContext>,
+    meta: ReportMeta,
+    split_tokens: usize,
+    token_counter: &Arc<dyn TokenCounter>,
+    config: &ContextConfig,
+) -> anyhow::Result<()> {
+    let tree: Vec<PathBuf> = files.iter().map(|f| f.relative_path.clone()).collect();
+
+    // Parts are measured as rendered: the tree, metadata and wrappers repeat in
+    // every part. The part numbers are sized for the worst case.
+    let placeholder = Some(PartInfo {
+        index: files.len().max(1),
+        total: files.len().max(1),
+    });
+    let rendered_tokens = |files: &[FileContext], meta: &ReportMeta| -> anyhow::Result<usize> {
+        let buffer = generate_output_buffer(files, meta, config, token_counter)?;
+        Ok(token_counter.count(&String::from_utf8_lossy(&buffer)))
+    };
+    let part_meta = ReportMeta {
+        part: placeholder,
+        tree: Some(tree.clone()),
+        ..meta.clone()
+    };
+    let overhead = rendered_tokens(&[], &part_meta)?;
+    if overhead >= split_tokens {
+        warn!(
+            "The tree and metadata of each part alone take ~{} tokens; parts will exceed --split-tokens {}.",
+            overhead, split_tokens
+        );
+    }
+    let bare_meta = ReportMeta {
+        part: placeholder,
+        tree: Some(Vec::new()),
+        omitted: Vec::new(),
+        ..meta.clone()
+    };
+    let bare = rendered_tokens(&[], &bare_meta)?;
+    let cost = |file: &FileContext| {
+        rendered_tokens(std::slice::from_ref(file), &bare_meta)
+            .map(|tokens| tokens.saturating_sub(bare))
+            .unwrap_or(file.token_count)
+    };
+    let parts = split_into_parts(files, split_tokens, overhead, &cost, token_counter.as_ref());
+    let total = parts.len();
+
+    let base = config.output_path.clone().unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "context_report.{}",
+            config.output_format.extension()
+        ))
+    });
+
+    for (i, part_files) in parts.into_iter().enumerate() {
+        let part_meta = ReportMeta {
+            part: Some(PartInfo {
+                index: i + 1,
+                total,
+            }),
+            tree: Some(tree.clone()),
+            ..meta.clone()
+        };
+        let buffer = generate_output_buffer(&part_files, &part_meta, config, token_counter)?;
+
+        if i == 0 && config.to_clipboard {
+            warn!(
+                "Split output: only part 1 of {} is copied to the clipboard.",
+                total
+            );
+            copy_to_clipboard(&buffer)?;
+        }
+
+        let path = part_path(&base, i + 1);
+        let mut buf_writer = BufWriter::new(File::create(&path)?);
+        buf_writer.write_all(&buffer)?;
+        info!(
+            "Part {}/{} ({} files) written to: {:?}",
+            i + 1,
+            total,
+            part_files.len(),
+            path
+        );
+    }
+
+    Ok(())
+}
+
+/// `report.xml` -> `report.part3.xml`
+fn part_path(base: &Path, index: usize) -> PathBuf {
+    let stem = base
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "context_report".to_string());
+    let file_name = match base.extension() {
+        Some(ext) => format!("{}.part{}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}.part{}", stem, index),
+    };
+    base.with_file_name(file_name)
+}
+
+fn copy_to_clipboard(buffer: &[u8]) -> anyhow::Result<()> {
+    let output_str = std::str::from_utf8(buffer)?;
+    match Clipboard::new() {
+        Ok(mut clipboard) => {
+            if let Err(e) = clipboard.set_text(output_str) {
+                error!("Failed to copy to clipboard: {}", e);
+            } else {
+                info!("Output copied to clipboard! ({} chars)", output_str.len());
+            }
+        }
+        Err(e) => error!("Could not access clipboard: {}", e),
+    }
+    Ok(())
+}
+
 fn generate_output_buffer(
-    files: &[context::core::content::FileContext],
+    files: &[FileContext],
+    meta: &ReportMeta,
     config: &ContextConfig,
+    token_counter: &Arc<dyn TokenCounter>,
 ) -> anyhow::Result<Vec<u8>> {
     let mut buffer = Vec::new();
 
     match config.output_format {
         OutputFormat::Xml => {
             let writer = XmlWriter::new();
-            writer.write(files, config, &mut buffer)?;
+            writer.write(files, meta, config, &mut buffer)?;
         }
         OutputFormat::Markdown => {
             let writer = MarkdownWriter::new();
-            writer.write(files, config, &mut buffer)?;
+            writer.write(files, meta, config, &mut buffer)?;
         }
         OutputFormat::Json => {
             let writer = JsonWriter::new();
-            writer.write(files, config, &mut buffer)?;
+            writer.write(files, meta, config, &mut buffer)?;
         }
         OutputFormat::Text => {
             let writer = TextWriter::new();
-            writer.write(files, config, &mut buffer)?;
+            writer.write(files, meta, config, &mut buffer)?;
+        }
+        OutputFormat::JsonlChunks => {
+            let writer = JsonlChunkWriter::new(token_counter.clone());
+            writer.write(files, meta, config, &mut buffer)?;
         }
     }
 
     Ok(buffer)
 }
 
+/// Parses a similarity threshold in `0.0..=1.0`.
+fn parse_similarity(value: &str) -> Result<f64, String> {
+    let threshold: f64 = value
+        .parse()
+        .map_err(|_| format!("`{}` is not a number", value))?;
+    if (0.0..=1.0).contains(&threshold) {
+        Ok(threshold)
+    } else {
+        Err("threshold must be between 0.0 and 1.0".to_string())
+    }
+}
+
+/// Checks that `--grep` is a valid regular expression.
+fn parse_regex(value: &str) -> Result<String, String> {
+    Regex::new(value).map_err(|e| e.to_string())?;
+    Ok(value.to_string())
+}
+
+/// Parses a point in time for the time filters: a duration before now or a local date.
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    parse_time_at(value, Local::now())
+}
+
+fn parse_time_at(value: &str, now: DateTime<Local>) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0).expect("midnight is valid");
+    let local = match value.to_ascii_lowercase().as_str() {
+        "today" => Some(midnight(now.date_naive())),
+        "yesterday" => Some(midnight(now.date_naive() - Duration::days(1))),
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(midnight)
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"))
+            .ok(),
+    };
+    if let Some(local) = local {
+        return Local
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| format!("`{}` does not exist in the local time zone", value));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("`{}` is not a duration or a date", value))?;
+    let duration = match unit.trim() {
+        "s" => Duration::try_seconds(number),
+        "m" | "min" => Duration::try_minutes(number),
+        "h" => Duration::try_hours(number),
+        "d" => Duration::try_days(number),
+        "w" => Duration::try_weeks(number),
+        other => return Err(format!("unknown duration unit `{}` (s, m, h, d, w)", other)),
+    };
+    duration
+        .and_then(|duration| now.checked_sub_signed(duration))
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| format!("`{}` is too far in the past", value))
+}
+
+/// Parses a size in bytes with an optional binary unit: `4096`, `512K`, `10MB`, `1.5GiB`.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("`{}` is not a size", value))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        other => return Err(format!("unknown size unit `{}`", other)),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
 fn init_logging(verbosity: u8) {
     let level = match verbosity {
         0 => Level::WARN,
@@ -253,6 +989,51 @@ fn init_logging(verbosity: u8) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("1.5 MB"), Ok(1536 * 1024));
+        assert!(parse_size("10 parsecs").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let now = Local.with_ymd_and_hms(2024, 5, 10, 15, 30, 0).unwrap();
+        let local = |y, m, d, h, min| {
+            Local
+                .with_ymd_and_hms(y, m, d, h, min, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        assert_eq!(parse_time_at("2h", now), Ok(local(2024, 5, 10, 13, 30)));
+        assert_eq!(parse_time_at("1w", now), Ok(local(2024, 5, 3, 15, 30)));
+        assert_eq!(parse_time_at("today", now), Ok(local(2024, 5, 10, 0, 0)));
+        assert_eq!(
+            parse_time_at("2024-05-01", now),
+            Ok(local(2024, 5, 1, 0, 0))
+        );
+        assert_eq!(
+            parse_time_at("2024-05-01T14:05", now),
+            Ok(local(2024, 5, 1, 14, 5))
+        );
+        assert!(parse_time_at("3 fortnights", now).is_err());
+        assert!(parse_time_at("999999999999w", now).is_err());
+        assert!(parse_time_at("99999999999999999999d", now).is_err());
+    }
+
+    #[test]
+    fn test_part_path_naming() {
+        assert_eq!(
+            part_path(Path::new("out/report.xml"), 2),
+            PathBuf::from("out/report.part2.xml")
+        );
+        assert_eq!(
+            part_path(Path::new("report"), 1),
+            PathBuf::from("report.part1")
+        );
+    }
+
     #[test]
     fn verify_cli() {
         use clap::CommandFactory;
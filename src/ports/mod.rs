@@ -2,4 +2,5 @@
 
 pub mod reader;
 pub mod scanner;
+pub mod tokenizer;
 pub mod writer;
@@ -0,0 +1,8 @@
+/// Interface for counting the tokens a text will consume in a model's context window.
+pub trait TokenCounter: Send + Sync {
+    /// Returns the number of tokens the given text encodes to.
+    fn count(&self, text: &str) -> usize;
+
+    /// Short identifier of the encoding (e.g. `o200k`), used in report metadata.
+    fn name(&self) -> &str;
+}
@@ -1,5 +1,6 @@
 use crate::core::config::ContextConfig;
 use crate::core::content::FileContext;
+use crate::core::report::ReportMeta;
 use anyhow::Result;
 use std::io::Write;
 
@@ -9,6 +10,7 @@ pub trait ContextWriter {
     fn write<W: Write>(
         &self,
         files: &[FileContext],
+        meta: &ReportMeta,
         config: &ContextConfig,
         writer: W,
     ) -> Result<()>;
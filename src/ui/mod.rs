@@ -5,22 +5,20 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::prelude::*;
-use std::collections::HashSet;
 use std::io;
-use std::path::PathBuf;
 
 pub mod state;
 pub mod view;
 
 use crate::core::config::ContextConfig;
 use crate::core::file::FileNode;
-use crate::ui::state::App;
+use crate::ui::state::{App, Selection};
 
 pub fn run_tui(
     files: &[FileNode],
     root_path: &std::path::Path,
     initial_config: ContextConfig,
-) -> Result<Option<(HashSet<PathBuf>, ContextConfig)>> {
+) -> Result<Option<(Selection, ContextConfig)>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
@@ -34,9 +32,7 @@ pub fn run_tui(
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
-    if let Err(err) = res {
-        return Err(err);
-    }
+    res?;
 
     if app.confirmed {
         Ok(Some((app.get_selected_paths(), app.config)))
@@ -54,14 +50,24 @@ fn run_app_loop(
 
         if event::poll(std::time::Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
+                if key.kind == KeyEventKind::Press && app.fragment_input.is_some() {
+                    match key.code {
+                        KeyCode::Enter => app.apply_fragment_input(),
+                        KeyCode::Esc => app.cancel_fragment_input(),
+                        KeyCode::Backspace => app.pop_fragment_char(),
+                        KeyCode::Char(c) => app.push_fragment_char(c),
+                        _ => {}
+                    }
+                } else if key.kind == KeyEventKind::Press {
                     match key.code {
                         KeyCode::Char('q') | KeyCode::Esc => app.quit(),
                         KeyCode::Enter => app.confirm(),
                         KeyCode::Char('c') => app.toggle_clipboard(),
                         KeyCode::Char('m') => app.toggle_minify(),
+                        KeyCode::Char('s') => app.toggle_strip_comments(),
                         KeyCode::Char('f') => app.cycle_format(),
                         KeyCode::Char('o') => app.toggle_output_destination(),
+                        KeyCode::Char('r') => app.start_fragment_input(),
                         KeyCode::Up => app.move_up(),
                         KeyCode::Down => app.move_down(),
                         KeyCode::Char(' ') => app.toggle_selection(),
@@ -74,4 +80,4 @@ fn run_app_loop(
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,9 +1,12 @@
 use crate::core::config::{ContextConfig, OutputFormat};
-use crate::core::file::FileNode;
+use crate::core::file::{FileNode, Fragment};
 use ratatui::widgets::ListState;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+/// Selected files, by relative path, with the fragment to read of each.
+pub type Selection = HashMap<PathBuf, Option<Fragment>>;
+
 pub struct UiNode {
     pub path: PathBuf,
     pub name: String,
@@ -12,6 +15,8 @@ pub struct UiNode {
     pub selected: bool,
     pub depth: usize,
     pub children: Vec<usize>,
+    /// Part of the file to read (`:START-END` or `#symbol`), files only.
+    pub fragment: Option<Fragment>,
 }
 
 pub struct App {
@@ -23,6 +28,8 @@ pub struct App {
     pub confirmed: bool,
     pub config: ContextConfig,
     pub default_filename: String,
+    /// Text typed at the fragment prompt, while it is open.
+    pub fragment_input: Option<String>,
 }
 
 impl App {
@@ -52,6 +59,7 @@ impl App {
                         selected: true,
                         depth,
                         children: Vec::new(),
+                        fragment: None,
                     };
 
                     let idx = nodes.len();
@@ -67,6 +75,9 @@ impl App {
                 }
                 parent_idx = Some(path_to_index[&current_path]);
             }
+            if let Some(idx) = parent_idx {
+                nodes[idx].fragment = file.fragment.clone();
+            }
         }
 
         let mut app = Self {
@@ -78,6 +89,7 @@ impl App {
             confirmed: false,
             config,
             default_filename: "context_report".to_string(),
+            fragment_input: None,
         };
 
         app.update_view();
@@ -167,6 +179,57 @@ impl App {
         self.list_state.select(Some(i));
     }
 
+    fn current_file(&self) -> Option<usize> {
+        let selected_idx = self.list_state.selected()?;
+        let &node_idx = self.view_items.get(selected_idx)?;
+        (!self.nodes[node_idx].is_dir).then_some(node_idx)
+    }
+
+    /// Opens the prompt for the highlighted file, prefilled with its fragment.
+    pub fn start_fragment_input(&mut self) {
+        if let Some(idx) = self.current_file() {
+            let current = match &self.nodes[idx].fragment {
+                Some(Fragment::Lines(range)) => range.to_string(),
+                Some(fragment) => fragment.to_string(),
+                None => String::new(),
+            };
+            self.fragment_input = Some(current);
+        }
+    }
+
+    pub fn push_fragment_char(&mut self, c: char) {
+        if let Some(input) = &mut self.fragment_input {
+            input.push(c);
+        }
+    }
+
+    pub fn pop_fragment_char(&mut self) {
+        if let Some(input) = &mut self.fragment_input {
+            input.pop();
+        }
+    }
+
+    /// Applies the prompt: `START-END` or `#symbol` sets the fragment, an empty
+    /// input clears it and anything else keeps the prompt open.
+    pub fn apply_fragment_input(&mut self) {
+        let (Some(input), Some(idx)) = (&self.fragment_input, self.current_file()) else {
+            self.fragment_input = None;
+            return;
+        };
+        if input.trim().is_empty() {
+            self.nodes[idx].fragment = None;
+        } else if let Some(fragment) = Fragment::parse(input) {
+            self.nodes[idx].fragment = Some(fragment);
+        } else {
+            return;
+        }
+        self.fragment_input = None;
+    }
+
+    pub fn cancel_fragment_input(&mut self) {
+        self.fragment_input = None;
+    }
+
     pub fn toggle_clipboard(&mut self) {
         self.config.to_clipboard = !self.config.to_clipboard;
     }
@@ -175,15 +238,17 @@ impl App {
         self.config.minify = !self.config.minify;
     }
 
+    pub fn toggle_strip_comments(&mut self) {
+        self.config.strip_comments = !self.config.strip_comments;
+    }
+
     pub fn toggle_output_destination(&mut self) {
         if self.config.output_path.is_some() {
             self.config.output_path = None;
         } else {
-            let ext = self.get_current_extension();
-            self.config.output_path = Some(PathBuf::from(format!(
-                "{}.{}",
-                self.default_filename, ext
-            )));
+            let ext = self.config.output_format.extension();
+            self.config.output_path =
+                Some(PathBuf::from(format!("{}.{}", self.default_filename, ext)));
         }
     }
 
@@ -192,28 +257,16 @@ impl App {
             OutputFormat::Xml => OutputFormat::Markdown,
             OutputFormat::Markdown => OutputFormat::Json,
             OutputFormat::Json => OutputFormat::Text,
-            OutputFormat::Text => OutputFormat::Xml,
+            OutputFormat::Text => OutputFormat::JsonlChunks,
+            OutputFormat::JsonlChunks => OutputFormat::Xml,
         };
 
-        // Calculate extension before mutably borrowing output_path
-        let ext = {
-            let ext = self.get_current_extension();
-            ext.to_string()
-        };
+        let ext = self.config.output_format.extension();
         if let Some(path) = &mut self.config.output_path {
             path.set_extension(ext);
         }
     }
 
-    fn get_current_extension(&self) -> &str {
-        match self.config.output_format {
-            OutputFormat::Xml => "xml",
-            OutputFormat::Markdown => "md",
-            OutputFormat::Json => "json",
-            OutputFormat::Text => "txt",
-        }
-    }
-
     pub fn confirm(&mut self) {
         self.confirmed = true;
         self.should_quit = true;
@@ -223,11 +276,11 @@ impl App {
         self.should_quit = true;
     }
 
-    pub fn get_selected_paths(&self) -> HashSet<PathBuf> {
+    pub fn get_selected_paths(&self) -> Selection {
         self.nodes
             .iter()
             .filter(|n| n.selected && !n.is_dir)
-            .map(|n| n.path.clone())
+            .map(|n| (n.path.clone(), n.fragment.clone()))
             .collect()
     }
-}
\ No newline at end of file
+}
@@ -27,7 +27,12 @@ pub fn render_app(frame: &mut Frame, app: &mut App) {
             };
             let check = if node.selected { "[x] " } else { "[ ] " };
 
-            let content = format!("{}{}{}{}", indent, check, icon, node.name);
+            let fragment = node
+                .fragment
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            let content = format!("{}{}{}{}{}", indent, check, icon, node.name, fragment);
             ListItem::new(content)
         })
         .collect();
@@ -50,6 +55,11 @@ pub fn render_app(frame: &mut Frame, app: &mut App) {
         "[OFF]"
     };
     let min_str = if app.config.minify { "[ON]" } else { "[OFF]" };
+    let strip_str = if app.config.strip_comments {
+        "[ON]"
+    } else {
+        "[OFF]"
+    };
 
     let out_str = match &app.config.output_path {
         Some(p) => format!("File ({})", p.display()),
@@ -57,13 +67,21 @@ pub fn render_app(frame: &mut Frame, app: &mut App) {
     };
 
     let status_text = format!(
-        " Fmt(f): {} | Dest(o): {} | Clip(c): {} | Min(m): {} | Confirm: Enter ",
-        fmt_str, out_str, clip_str, min_str
+        " Fmt(f): {} | Dest(o): {} | Clip(c): {} | Min(m): {} | Strip(s): {} | Range(r) | Confirm: Enter ",
+        fmt_str, out_str, clip_str, min_str, strip_str
     );
 
+    let (title, status_text) = match &app.fragment_input {
+        Some(input) => (
+            " Lines START-END or #symbol (Enter: apply, empty clears; Esc: cancel) ",
+            format!(" {}_", input),
+        ),
+        None => (" Controls ", status_text),
+    };
+
     let help = Paragraph::new(status_text)
-        .block(Block::default().borders(Borders::ALL).title(" Controls "))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .style(Style::default().fg(Color::Cyan));
 
     frame.render_widget(help, chunks[1]);
-}
\ No newline at end of file
+}
@@ -44,3 +44,233 @@ fn test_cli_filtering_flow() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_cli_token_budget_lists_omitted_files() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path();
+
+    fs::write(root.join("small.rs"), "fn a() {}\n")?;
+    fs::write(root.join("large.rs"), "fn b() { let x = 1; }\n".repeat(200))?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+
+    cmd.arg(root).arg("--max-tokens").arg("50");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("<file path=\"small.rs\""))
+        .stdout(predicate::str::contains("<omitted_files>"))
+        .stdout(predicate::str::is_match(
+            r#"<file path="large.rs" tokens="\d+" reason="token_budget"/>"#,
+        )?);
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_skeleton_mode() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path();
+
+    fs::write(
+        root.join("lib.rs"),
+        "/// Adds.\npub fn add(a: i32) -> i32 {\n    a + 1\n}\n",
+    )?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+
+    cmd.arg(root).arg("--skeleton");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("mode=\"skeleton\""))
+        .stdout(predicate::str::contains(
+            "pub fn add(a: i32) -> i32 { ... }",
+        ))
+        .stdout(predicate::str::contains("a + 1").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_dedupes_identical_files() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path();
+
+    fs::create_dir_all(root.join("a"))?;
+    fs::create_dir_all(root.join("b"))?;
+    fs::write(root.join("a/x.rs"), "fn x() {}\n")?;
+    fs::write(root.join("b/x.rs"), "fn x() {}\n")?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+    cmd.arg(root);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "<file path=\"b/x.rs\" duplicate_of=\"a/x.rs\"/>",
+        ))
+        .stdout(predicate::str::contains("<duplicates files=\"1\""));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+    cmd.arg(root).arg("--no-dedup");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("duplicate_of").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_multiple_roots() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let api = dir.path().join("api");
+    let web = dir.path().join("web");
+
+    fs::create_dir_all(api.join("src"))?;
+    fs::create_dir_all(web.join("src"))?;
+    fs::write(api.join("src/main.rs"), "fn main() {}\n")?;
+    fs::write(web.join("src/main.ts"), "console.log(1);\n")?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+    cmd.arg(&api).arg(&web);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("<root label=\"api\""))
+        .stdout(predicate::str::contains("<root label=\"web\""))
+        .stdout(predicate::str::contains(
+            "<file path=\"api/src/main.rs\" root=\"api\"",
+        ))
+        .stdout(predicate::str::contains(
+            "<file path=\"web/src/main.ts\" root=\"web\"",
+        ))
+        .stdout(predicate::str::contains("├── api\n│   └── src"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_multiple_roots_own_project_config() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let api = dir.path().join("api");
+    let web = dir.path().join("web");
+
+    fs::create_dir_all(&api)?;
+    fs::create_dir_all(&web)?;
+    fs::write(
+        api.join(".context.toml"),
+        "[noise]\nfiles = [\"notes.md\"]\n",
+    )?;
+    fs::write(api.join("notes.md"), "api notes\n")?;
+    fs::write(web.join("notes.md"), "web notes\n")?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+    cmd.arg(&web).arg(&api);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("web notes"))
+        .stdout(predicate::str::contains("api notes").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_files_from_stdin() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path();
+
+    fs::write(root.join("picked.rs"), "line 1\nline 2\nline 3\n")?;
+    fs::write(root.join("other.rs"), "fn other() {}\n")?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+    cmd.current_dir(root)
+        .arg("--files-from")
+        .arg("-")
+        .write_stdin("picked.rs:2-2\n");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "<file path=\"picked.rs\" language=\"rs\" lines=\"2-2\"><![CDATA[line 2\n]]>",
+        ))
+        .stdout(predicate::str::contains("other.rs").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_grep_excerpts() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path();
+
+    fs::write(root.join("hit.rs"), "a\nb\nTODO: fix\nc\nd\ne\n")?;
+    fs::write(root.join("miss.rs"), "fn clean() {}\n")?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+    cmd.arg(root)
+        .arg("--grep")
+        .arg("TODO")
+        .arg("--grep-context")
+        .arg("1");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "<excerpt start=\"2\" end=\"4\"><![CDATA[b\nTODO: fix\nc\n]]></excerpt>",
+        ))
+        .stdout(predicate::str::contains("miss.rs").not());
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_grep_excerpts_as_jsonl_chunks() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path();
+
+    fs::write(
+        root.join("hit.rs"),
+        "a\nb\nTODO: one\nc\nd\ne\nf\nTODO: two\n",
+    )?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+    cmd.arg(root)
+        .arg("--grep")
+        .arg("TODO")
+        .arg("--grep-context")
+        .arg("0")
+        .arg("--format")
+        .arg("jsonl-chunks");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"start_line\":3,\"end_line\":3,\"start_byte\":4,\"end_byte\":14,",
+        ))
+        .stdout(predicate::str::contains(
+            "\"start_line\":8,\"end_line\":8,\"start_byte\":22,\"end_byte\":32,",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_symbol_and_range_arguments() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let root = dir.path();
+
+    fs::write(
+        root.join("lib.rs"),
+        "use std::fmt;\n\n/// Runs.\npub fn run() {\n    go();\n}\n\nfn stop() {}\n",
+    )?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context"));
+    cmd.current_dir(root).arg("lib.rs#run").arg("lib.rs:8");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "symbol=\"run\" lines=\"3-6\"><![CDATA[/// Runs.\npub fn run() {\n    go();\n}\n]]>",
+        ))
+        .stdout(predicate::str::contains(
+            "lines=\"8-8\"><![CDATA[fn stop() {}\n]]>",
+        ));
+
+    Ok(())
+}